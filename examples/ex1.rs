@@ -6,7 +6,8 @@ fn main() {
     kv.insert(
         Key::Text("demo".into()),
         OwnedValue::Integer(123),
-    );
+    )
+    .unwrap();
 
     println!("Stored demo=123");
 }
@@ -20,12 +20,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("Vorher: name nicht gesetzt");
     }
 
-    store.insert(ktxt("name"), OwnedValue::Text("Richard".into()));
-    store.insert(ktxt("answer"), OwnedValue::Integer(42));
+    store.insert(ktxt("name"), OwnedValue::Text("Richard".into()))?;
+    store.insert(ktxt("answer"), OwnedValue::Integer(42))?;
 
     store.persist_to_file(path)?;
 
     println!("Neue Werte gespeichert.");
+    println!("Aufbau der Einträge:\n{}", store.debug_hexdump());
     println!("Datei '{}' bleibt erhalten.", path);
 
     Ok(())
@@ -3,7 +3,7 @@ use kv_store::{KvStore, Key, OwnedValue, KvResult};
 fn main() -> KvResult<()> {
     let mut store = KvStore::new();
 
-    store.insert(Key::Text("age".into()), OwnedValue::Integer(20));
+    store.insert(Key::Text("age".into()), OwnedValue::Integer(20))?;
 
     let age: Option<OwnedValue> = store.get_owned(&Key::Text("age".into()))?;
     match age {
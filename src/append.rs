@@ -0,0 +1,190 @@
+//! Append-only persistence: every write goes straight to the end of the store file.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    read_file, scan_records, serialize_key, serialize_tombstone, serialize_value,
+    strip_file_header, BorrowedValue, Codec, Key, KvError, KvResult, KvStore, OwnedValue,
+};
+
+/// A store whose writes are appended to its file as they happen, opened with
+/// [`KvStore::open_appending`].
+///
+/// The file uses the regular record format, with deletes recorded as tombstone entries.
+/// Reopening replays it, later records win. With [`set_sync`](AppendingStore::set_sync)
+/// every write is `fsync`ed before it becomes visible.
+///
+/// Every record gets a sequence number, its 1-based position in the file, so numbers
+/// are strictly increasing and continue where they left off after reopening.
+pub struct AppendingStore {
+    store: KvStore,
+    file: File,
+    // Length of the file up to the last complete record.
+    file_len: u64,
+    sync: bool,
+    // Sequence number of the next record; atomic so current_seq needs no exclusive access.
+    next_seq: AtomicU64,
+}
+
+impl KvStore {
+    /// Opens (or creates) the store file at `path` for appending and replays it.
+    ///
+    /// Any damage is an error, including a record cut off at the end of the file (e.g. by
+    /// a crash mid-write), which fails with [`KvError::UnexpectedEof`]. Such files can be
+    /// opened with [`open_appending_truncating`](KvStore::open_appending_truncating).
+    pub fn open_appending(path: &str) -> KvResult<AppendingStore> {
+        Self::open_log(path, false).map(|(log, _)| log)
+    }
+
+    /// Like [`open_appending`](KvStore::open_appending), but a record cut off at the end
+    /// of the file is dropped and the file truncated to the last complete record.
+    /// Returns the store and the number of bytes dropped.
+    ///
+    /// A corrupted length field in the middle of the file looks the same as a cut-off
+    /// record, so all records after it are dropped too. Check the returned count before
+    /// relying on the result.
+    pub fn open_appending_truncating(path: &str) -> KvResult<(AppendingStore, u64)> {
+        Self::open_log(path, true)
+    }
+
+    fn open_log(path: &str, truncate_torn_tail: bool) -> KvResult<(AppendingStore, u64)> {
+        let bytes = read_file(path)?.unwrap_or_default();
+
+        let (records, codec) = strip_file_header(&bytes, Codec::default())?;
+        let header_len = bytes.len() - records.len();
+
+        let mut store = KvStore::new();
+        store.codec = codec;
+
+        let mut valid_end = 0;
+        let mut record_count = 0;
+        let replayed = scan_records(records, header_len, codec, |key, range| {
+            store.apply_record(key, &records[range.clone()])?;
+            valid_end = range.end;
+            record_count += 1;
+            Ok(())
+        });
+
+        match replayed {
+            Ok(()) => {}
+            Err(KvError::UnexpectedEof { .. }) if truncate_torn_tail => {}
+            Err(e) => return Err(e),
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let file_len = (header_len + valid_end) as u64;
+        let dropped = bytes.len() as u64 - file_len;
+        if dropped > 0 {
+            file.set_len(file_len)?;
+            file.sync_all()?;
+        }
+
+        let log = AppendingStore {
+            store,
+            file,
+            file_len,
+            sync: false,
+            next_seq: AtomicU64::new(record_count + 1),
+        };
+        Ok((log, dropped))
+    }
+}
+
+impl AppendingStore {
+    /// Whether each write is `fsync`ed before returning. Off by default.
+    pub fn set_sync(&mut self, sync: bool) {
+        self.sync = sync;
+    }
+
+    pub fn sync(&self) -> bool {
+        self.sync
+    }
+
+    /// Sequence number of the last record written, 0 for an empty file.
+    pub fn current_seq(&self) -> u64 {
+        self.next_seq.load(Ordering::Acquire) - 1
+    }
+
+    /// Appends `value` under `key` to the file, then to the store. Returns the value it
+    /// replaced, if any; one that fails to decode is returned as `None`.
+    pub fn insert(&mut self, key: Key, value: OwnedValue) -> KvResult<Option<OwnedValue>> {
+        let codec = self.store.codec;
+        let previous = self.store.get_owned(&key).unwrap_or(None);
+
+        let mut entry = Vec::new();
+        serialize_value(&value, codec, &mut entry);
+        codec.check_value_size(entry.len())?;
+
+        let mut record = Vec::new();
+        serialize_key(&key, codec, &mut record);
+        record.extend_from_slice(&entry);
+        self.write_record(&record)?;
+
+        let offset = self.store.data.len();
+        self.store.data.extend_from_slice(&entry);
+        self.store.index.insert(key, offset);
+        Ok(previous)
+    }
+
+    /// Records a tombstone for `key` and removes it. Returns `false` (and writes
+    /// nothing) if the key was not present.
+    pub fn delete(&mut self, key: &Key) -> KvResult<bool> {
+        if !self.store.contains_key(key) {
+            return Ok(false);
+        }
+
+        let codec = self.store.codec;
+        let mut record = Vec::new();
+        serialize_key(key, codec, &mut record);
+        serialize_tombstone(codec, &mut record);
+        self.write_record(&record)?;
+
+        Ok(self.store.delete(key))
+    }
+
+    // A failed write is cut off again, so the next record does not follow garbage.
+    fn write_record(&mut self, record: &[u8]) -> KvResult<()> {
+        let written = self.file.write_all(record).and_then(|()| {
+            if self.sync {
+                self.file.sync_data()?;
+            }
+            Ok(())
+        });
+
+        if let Err(e) = written {
+            let _ = self.file.set_len(self.file_len);
+            return Err(e.into());
+        }
+
+        self.file_len += record.len() as u64;
+        self.next_seq.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+
+    pub fn get_borrowed(&self, key: &Key) -> KvResult<Option<BorrowedValue<'_>>> {
+        self.store.get_borrowed(key)
+    }
+
+    pub fn get_owned(&self, key: &Key) -> KvResult<Option<OwnedValue>> {
+        self.store.get_owned(key)
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.store.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.store.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.index.is_empty()
+    }
+
+    /// The in-memory state, for everything that does not write.
+    pub fn store(&self) -> &KvStore {
+        &self.store
+    }
+}
@@ -14,7 +14,7 @@ fn main() {
     let command = &args[2];
     
     let result = match command.as_str() {
-        "list" => cmd_list(file),
+        "list" => cmd_list(file, args.get(3).is_some_and(|a| a == "--json")),
         "new" => {
             if args.len() < 5 {
                 eprintln!("Error: 'new' requires <title> and <body>");
@@ -31,6 +31,30 @@ fn main() {
             }
             cmd_show(file, &args[3])
         }
+        "delete" => {
+            if args.len() < 4 {
+                eprintln!("Error: 'delete' requires <id>");
+                print_usage();
+                process::exit(1);
+            }
+            cmd_delete(file, &args[3])
+        }
+        "edit" => {
+            if args.len() < 6 {
+                eprintln!("Error: 'edit' requires <id>, <title> and <body>");
+                print_usage();
+                process::exit(1);
+            }
+            cmd_edit(file, &args[3], &args[4], &args[5])
+        }
+        "tag" => {
+            if args.len() < 6 || (args[3] != "add" && args[3] != "rm") {
+                eprintln!("Error: 'tag' requires add|rm, <id> and <tag>");
+                print_usage();
+                process::exit(1);
+            }
+            cmd_tag(file, args[3] == "add", &args[4], &args[5])
+        }
         _ => {
             eprintln!("Error: unknown command '{}'", command);
             print_usage();
@@ -48,15 +72,24 @@ fn print_usage() {
     eprintln!("Usage: notes_cli <FILE> <COMMAND> [ARGS...]");
     eprintln!();
     eprintln!("Commands:");
-    eprintln!("  list                  List all notes");
+    eprintln!("  list [--json]         List all notes");
     eprintln!("  new <title> <body>    Create a new note");
     eprintln!("  show <id>             Show a note by ID");
+    eprintln!("  delete <id>           Delete a note by ID");
+    eprintln!("  edit <id> <title> <body>  Replace title and body of a note");
+    eprintln!("  tag add <id> <tag>    Add a tag to a note");
+    eprintln!("  tag rm <id> <tag>     Remove a tag from a note");
 }
 
-fn cmd_list(file: &str) -> Result<(), Box<dyn std::error::Error>> {
+fn cmd_list(file: &str, json: bool) -> Result<(), Box<dyn std::error::Error>> {
     let store = NoteStore::open(file)?;
     let metas = store.list_meta()?;
     
+    if json {
+        println!("{}", serde_json::to_string(&metas)?);
+        return Ok(());
+    }
+    
     for meta in metas {
         println!("{}  {}", meta.id, meta.title);
     }
@@ -92,3 +125,79 @@ fn cmd_show(file: &str, id_str: &str) -> Result<(), Box<dyn std::error::Error>>
     
     Ok(())
 }
+
+fn cmd_delete(file: &str, id_str: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let id: u64 = id_str.parse()
+        .map_err(|_| format!("invalid id: {}", id_str))?;
+    
+    let mut store = NoteStore::open(file)?;
+    
+    if store.delete(id)? {
+        store.save(file)?;
+        println!("deleted {}", id);
+    } else {
+        println!("not found");
+    }
+    
+    Ok(())
+}
+
+fn cmd_edit(file: &str, id_str: &str, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let id: u64 = id_str.parse()
+        .map_err(|_| format!("invalid id: {}", id_str))?;
+    
+    let mut store = NoteStore::open(file)?;
+    
+    let mut note = match store.get(id)? {
+        Some(note) => note,
+        None => {
+            println!("not found");
+            process::exit(1);
+        }
+    };
+    
+    note.title = title.to_string();
+    note.body = body.to_string();
+    store.update(note)?;
+    store.save(file)?;
+    
+    println!("updated {}", id);
+    
+    Ok(())
+}
+
+fn cmd_tag(file: &str, add: bool, id_str: &str, tag: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let id: u64 = id_str.parse()
+        .map_err(|_| format!("invalid id: {}", id_str))?;
+    let tag = tag.trim();
+    
+    let mut store = NoteStore::open(file)?;
+    
+    let mut note = match store.get(id)? {
+        Some(note) => note,
+        None => {
+            println!("not found");
+            process::exit(1);
+        }
+    };
+    
+    let before = note.tags.len();
+    if add {
+        if !tag.is_empty() && !note.tags.iter().any(|t| t == tag) {
+            note.tags.push(tag.to_string());
+        }
+    } else {
+        note.tags.retain(|t| t != tag);
+    }
+    
+    let tags = note.tags.join(", ");
+    // No-ops leave the note (and its `updated_at`) and the file untouched.
+    if note.tags.len() != before {
+        store.update(note)?;
+        store.save(file)?;
+    }
+    
+    println!("{}  {}", id, tags);
+    
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -10,7 +10,7 @@ use ratatui::{
     Terminal,
 };
 use std::{env, io, fs, process::Command};
-use kv_store::notes::NoteStore;
+use kv_store::notes::{NoteMeta, NoteStore};
 
 struct AppState {
     selected: usize,
@@ -18,9 +18,17 @@ struct AppState {
     in_search: bool,
     in_new: bool,
     new_title: String,
+    // Second step of note creation: the title is done, the body is being typed.
+    new_in_body: bool,
+    new_body: String,
     error: Option<String>,
     confirm_delete: bool,
     delete_id: Option<u64>,
+    preview_scroll: u16,
+    // Note shown in the preview at the last redraw; the scroll resets when it changes.
+    preview_id: Option<u64>,
+    // Inner height of the preview pane at the last redraw, used as the page size.
+    preview_height: u16,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -65,9 +73,14 @@ where
         in_search: false,
         in_new: false,
         new_title: String::new(),
+        new_in_body: false,
+        new_body: String::new(),
         error: None,
         confirm_delete: false,
         delete_id: None,
+        preview_scroll: 0,
+        preview_id: None,
+        preview_height: 0,
     };
 
     loop {
@@ -88,19 +101,10 @@ where
                 .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
                 .split(main_area);
 
-            let filtered = if state.search.is_empty() {
-                metas.clone()
-            } else {
-                let search_lower = state.search.to_lowercase();
-                metas
-                    .iter()
-                    .filter(|m| {
-                        m.title.to_lowercase().contains(&search_lower)
-                            || m.tags.iter().any(|t| t.to_lowercase().contains(&search_lower))
-                    })
-                    .cloned()
-                    .collect()
-            };
+            let filtered = filter_metas(&metas, &state.search);
+            // The list can shrink under the selection (search edits, deletes); clamp on
+            // every redraw so indexing below stays in bounds.
+            state.selected = state.selected.min(filtered.len().saturating_sub(1));
 
             let list_text = if !filtered.is_empty() {
                 filtered
@@ -120,8 +124,9 @@ where
 
             let preview_text = if let Some(ref err) = state.error {
                 format!("Error: {}", err)
-            } else if !filtered.is_empty() {
-                let meta = &filtered[state.selected];
+            } else if state.in_new && state.new_in_body {
+                format!("{}\n\n{}_", state.new_title.trim(), state.new_body)
+            } else if let Some(meta) = filtered.get(state.selected) {
                 match store.get(meta.id) {
                     Ok(Some(note)) => format!("{}\n\n{}", note.title, note.body),
                     Ok(None) => "Note not found".to_string(),
@@ -137,7 +142,18 @@ where
                 .block(Block::default().title("Notes").borders(Borders::ALL));
             f.render_widget(list_widget, main_split[0]);
 
+            let shown_id = filtered.get(state.selected).map(|m| m.id);
+            if shown_id != state.preview_id {
+                state.preview_id = shown_id;
+                state.preview_scroll = 0;
+            }
+            state.preview_height = main_split[1].height.saturating_sub(2);
+            // Keep at least the last line visible
+            let max_scroll = preview_text.lines().count().saturating_sub(1);
+            state.preview_scroll = state.preview_scroll.min(max_scroll.min(u16::MAX as usize) as u16);
+
             let preview_widget = Paragraph::new(preview_text)
+                .scroll((state.preview_scroll, 0))
                 .block(Block::default().title("Preview").borders(Borders::ALL));
             f.render_widget(preview_widget, main_split[1]);
 
@@ -161,14 +177,16 @@ where
                 }
             }
 
-            let status_text = if state.in_new {
-                format!("New title: {} (Enter=save, Esc=cancel)", state.new_title)
+            let status_text = if state.in_new && state.new_in_body {
+                "New body: type in preview (Enter=newline, Ctrl+S=save, Esc=cancel)".to_string()
+            } else if state.in_new {
+                format!("New title: {} (Enter=next, Esc=cancel)", state.new_title)
             } else if state.in_search {
                 format!("Search: {}", state.search)
             } else if state.confirm_delete {
                 "Confirm deletion: y=yes, n/Esc=cancel".to_string()
             } else {
-                format!("File: {} | q: quit | /: search | n: new | d: delete | e: edit", file_path)
+                format!("File: {} | q: quit | /: search | n: new | d: delete | e: edit | J/K: scroll", file_path)
             };
             let status = Paragraph::new(status_text);
             f.render_widget(status, chunks[1]);
@@ -193,12 +211,9 @@ where
                                                 match store.list_meta() {
                                                     Ok(new_metas) => {
                                                         metas = new_metas;
-                                                        // Clamp selected index
-                                                        if !metas.is_empty() && state.selected >= metas.len() {
-                                                            state.selected = metas.len() - 1;
-                                                        } else if metas.is_empty() {
-                                                            state.selected = 0;
-                                                        }
+                                                        // Clamp selected index to the (possibly filtered) list
+                                                        let filtered_len = filter_metas(&metas, &state.search).len();
+                                                        state.selected = state.selected.min(filtered_len.saturating_sub(1));
                                                         state.error = None;
                                                     }
                                                     Err(e) => {
@@ -226,48 +241,72 @@ where
                         _ => {}
                     }
                 } else if state.in_new {
+                    let save = state.new_in_body
+                        && key.code == KeyCode::Char('s')
+                        && key.modifiers.contains(KeyModifiers::CONTROL);
                     match key.code {
                         KeyCode::Esc => {
                             state.in_new = false;
+                            state.new_in_body = false;
                             state.error = None;
                         }
-                        KeyCode::Backspace => {
-                            state.new_title.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            state.new_title.push(c);
-                        }
-                        KeyCode::Enter => {
-                            let title = state.new_title.trim();
-                            if !title.is_empty() {
-                                match store.create(title.to_string(), String::new()) {
-                                    Ok(_id) => {
-                                        match store.save(file_path) {
-                                            Ok(_) => {
-                                                match store.list_meta() {
-                                                    Ok(new_metas) => {
-                                                        metas = new_metas;
-                                                        if !metas.is_empty() {
-                                                            state.selected = metas.len() - 1;
-                                                        }
-                                                        state.in_new = false;
-                                                        state.new_title.clear();
-                                                        state.error = None;
-                                                    }
-                                                    Err(e) => {
-                                                        state.error = Some(format!("Failed to reload: {}", e));
-                                                    }
+                        _ if save => {
+                            let title = state.new_title.trim().to_string();
+                            let body = state.new_body.trim_end().to_string();
+                            match store.create(title, body) {
+                                Ok(id) => {
+                                    match store.save(file_path) {
+                                        Ok(_) => {
+                                            match store.list_meta() {
+                                                Ok(new_metas) => {
+                                                    metas = new_metas;
+                                                    // Clear the filter so the new note is guaranteed to be listed
+                                                    state.search.clear();
+                                                    state.selected = metas
+                                                        .iter()
+                                                        .position(|m| m.id == id)
+                                                        .unwrap_or(0);
+                                                    state.in_new = false;
+                                                    state.new_in_body = false;
+                                                    state.new_title.clear();
+                                                    state.new_body.clear();
+                                                    state.error = None;
+                                                }
+                                                Err(e) => {
+                                                    state.error = Some(format!("Failed to reload: {}", e));
                                                 }
-                                            }
-                                            Err(e) => {
-                                                state.error = Some(format!("Failed to save: {}", e));
                                             }
                                         }
+                                        Err(e) => {
+                                            state.error = Some(format!("Failed to save: {}", e));
+                                        }
                                     }
-                                    Err(e) => {
-                                        state.error = Some(format!("Failed to create: {}", e));
-                                    }
                                 }
+                                Err(e) => {
+                                    state.error = Some(format!("Failed to create: {}", e));
+                                }
+                            }
+                        }
+                        KeyCode::Backspace => {
+                            if state.new_in_body {
+                                state.new_body.pop();
+                            } else {
+                                state.new_title.pop();
+                            }
+                        }
+                        KeyCode::Char(c) => {
+                            if state.new_in_body {
+                                state.new_body.push(c);
+                            } else {
+                                state.new_title.push(c);
+                            }
+                        }
+                        KeyCode::Enter => {
+                            if state.new_in_body {
+                                state.new_body.push('\n');
+                            } else if !state.new_title.trim().is_empty() {
+                                state.new_in_body = true;
+                                state.error = None;
                             }
                         }
                         _ => {}
@@ -277,18 +316,7 @@ where
                         KeyCode::Esc | KeyCode::Enter => {
                             state.in_search = false;
                             // Clamp selected to filtered length
-                            let filtered_len = if state.search.is_empty() {
-                                metas.len()
-                            } else {
-                                let search_lower = state.search.to_lowercase();
-                                metas
-                                    .iter()
-                                    .filter(|m| {
-                                        m.title.to_lowercase().contains(&search_lower)
-                                            || m.tags.iter().any(|t| t.to_lowercase().contains(&search_lower))
-                                    })
-                                    .count()
-                            };
+                            let filtered_len = filter_metas(&metas, &state.search).len();
                             if filtered_len > 0 && state.selected >= filtered_len {
                                 state.selected = filtered_len - 1;
                             }
@@ -306,7 +334,9 @@ where
                         KeyCode::Char('q') => return Ok(()),
                         KeyCode::Char('n') => {
                             state.in_new = true;
+                            state.new_in_body = false;
                             state.new_title.clear();
+                            state.new_body.clear();
                             state.error = None;
                         }
                         KeyCode::Char('/') => {
@@ -315,19 +345,7 @@ where
                         }
                         KeyCode::Char('d') => {
                             // Get the filtered list to find the actual note ID
-                            let filtered: Vec<_> = if state.search.is_empty() {
-                                metas.clone()
-                            } else {
-                                let search_lower = state.search.to_lowercase();
-                                metas
-                                    .iter()
-                                    .filter(|m| {
-                                        m.title.to_lowercase().contains(&search_lower)
-                                            || m.tags.iter().any(|t| t.to_lowercase().contains(&search_lower))
-                                    })
-                                    .cloned()
-                                    .collect()
-                            };
+                            let filtered = filter_metas(&metas, &state.search);
                             
                             if !filtered.is_empty() && state.selected < filtered.len() {
                                 let note_id = filtered[state.selected].id;
@@ -338,19 +356,7 @@ where
                         }
                         KeyCode::Char('e') => {
                             // Get the filtered list to find the actual note ID
-                            let filtered: Vec<_> = if state.search.is_empty() {
-                                metas.clone()
-                            } else {
-                                let search_lower = state.search.to_lowercase();
-                                metas
-                                    .iter()
-                                    .filter(|m| {
-                                        m.title.to_lowercase().contains(&search_lower)
-                                            || m.tags.iter().any(|t| t.to_lowercase().contains(&search_lower))
-                                    })
-                                    .cloned()
-                                    .collect()
-                            };
+                            let filtered = filter_metas(&metas, &state.search);
                             
                             if !filtered.is_empty() && state.selected < filtered.len() {
                                 let note_id = filtered[state.selected].id;
@@ -397,24 +403,20 @@ where
                                 }
                             }
                         }
+                        KeyCode::PageUp | KeyCode::Char('K') => {
+                            let page = state.preview_height.max(1);
+                            state.preview_scroll = state.preview_scroll.saturating_sub(page);
+                        }
+                        KeyCode::PageDown | KeyCode::Char('J') => {
+                            // Clamped to the body length on the next redraw
+                            let page = state.preview_height.max(1);
+                            state.preview_scroll = state.preview_scroll.saturating_add(page);
+                        }
                         KeyCode::Up | KeyCode::Char('k') => {
-                            if state.selected > 0 {
-                                state.selected -= 1;
-                            }
+                            state.selected = state.selected.saturating_sub(1);
                         }
                         KeyCode::Down | KeyCode::Char('j') => {
-                            let filtered_len = if state.search.is_empty() {
-                                metas.len()
-                            } else {
-                                let search_lower = state.search.to_lowercase();
-                                metas
-                                    .iter()
-                                    .filter(|m| {
-                                        m.title.to_lowercase().contains(&search_lower)
-                                            || m.tags.iter().any(|t| t.to_lowercase().contains(&search_lower))
-                                    })
-                                    .count()
-                            };
+                            let filtered_len = filter_metas(&metas, &state.search).len();
                             if state.selected + 1 < filtered_len {
                                 state.selected += 1;
                             }
@@ -426,6 +428,22 @@ where
         }
     }
 }
+// Notes whose title or a tag contains `search` (ignoring case); all notes if it is empty.
+fn filter_metas(metas: &[NoteMeta], search: &str) -> Vec<NoteMeta> {
+    if search.is_empty() {
+        return metas.to_vec();
+    }
+    let search_lower = search.to_lowercase();
+    metas
+        .iter()
+        .filter(|m| {
+            m.title.to_lowercase().contains(&search_lower)
+                || m.tags.iter().any(|t| t.to_lowercase().contains(&search_lower))
+        })
+        .cloned()
+        .collect()
+}
+
 fn edit_note_in_editor(note: &mut kv_store::notes::Note, os_hint: Option<&str>) -> Result<(), String> {
     
     // Get editor from environment or default based on OS hint
@@ -490,8 +508,6 @@ fn edit_note_in_editor(note: &mut kv_store::notes::Note, os_hint: Option<&str>)
     // Extract body (skip "Title: " line and the blank line after it)
     let body_start = if lines.len() > 2 && lines[1].trim().is_empty() {
         2
-    } else if lines.len() > 1 {
-        1
     } else {
         1
     };
@@ -0,0 +1,91 @@
+//! Encrypted store files (`encryption` feature).
+//!
+//! Layout: `K9KE`, a format version byte, a random 12-byte nonce, then the store in
+//! regular file format (see [`KvStore::to_bytes`]) sealed with ChaCha20-Poly1305. Magic
+//! and version are authenticated as associated data.
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+use crate::{create_file, read_file, Checksum, DecodeError, KvError, KvResult, KvStore, PersistOptions};
+
+const MAGIC: &[u8; 4] = b"K9KE";
+const VERSION: u8 = 1;
+const NONCE_BYTES: usize = 12;
+const PREFIX_BYTES: usize = MAGIC.len() + 1;
+
+impl KvStore {
+    /// Writes the store encrypted with `key`, with a fresh random nonce every time.
+    ///
+    /// Read it back with [`KvStore::load_from_file_encrypted`] (or its `_with_seed` /
+    /// `_with_checksum` variants for stores with those settings); the regular loaders
+    /// cannot read it.
+    pub fn persist_to_file_encrypted(&self, path: &str, key: &[u8; 32]) -> KvResult<()> {
+        use std::io::Write;
+
+        let mut prefix = [0u8; PREFIX_BYTES];
+        prefix[..MAGIC.len()].copy_from_slice(MAGIC);
+        prefix[MAGIC.len()] = VERSION;
+
+        let cipher = ChaCha20Poly1305::new(key.into());
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let plaintext = self.to_bytes();
+        let sealed = cipher
+            .encrypt(&nonce, Payload { msg: &plaintext, aad: &prefix })
+            .expect("ChaCha20-Poly1305 only rejects inputs beyond 256 GiB");
+
+        let mut file = create_file(path, &PersistOptions::default())?;
+        file.write_all(&prefix)?;
+        file.write_all(&nonce)?;
+        file.write_all(&sealed)?;
+        Ok(())
+    }
+
+    /// Loads a file written by [`KvStore::persist_to_file_encrypted`]. A missing file
+    /// yields an empty store.
+    ///
+    /// A wrong key, a modified file or a file that is not encrypted fails with
+    /// [`KvError::Decryption`]; nothing is decoded before the file is authenticated.
+    pub fn load_from_file_encrypted(path: &str, key: &[u8; 32]) -> KvResult<KvStore> {
+        Self::load_encrypted_into(KvStore::new(), path, key)
+    }
+
+    pub fn load_from_file_encrypted_with_seed(path: &str, key: &[u8; 32], seed: u32) -> KvResult<KvStore> {
+        Self::load_encrypted_into(KvStore::with_checksum_seed(seed), path, key)
+    }
+
+    pub fn load_from_file_encrypted_with_checksum(
+        path: &str,
+        key: &[u8; 32],
+        algorithm: Checksum,
+    ) -> KvResult<KvStore> {
+        Self::load_encrypted_into(KvStore::with_checksum(algorithm), path, key)
+    }
+
+    // The plaintext is a regular store file, so `store` has to match the writer's seed
+    // and checksum algorithm like for the unencrypted loaders.
+    fn load_encrypted_into(store: KvStore, path: &str, key: &[u8; 32]) -> KvResult<KvStore> {
+        let Some(bytes) = read_file(path)? else {
+            return Ok(store);
+        };
+
+        if bytes.len() < PREFIX_BYTES + NONCE_BYTES {
+            return Err(KvError::UnexpectedEof { offset: 0 });
+        }
+        let (prefix, rest) = bytes.split_at(PREFIX_BYTES);
+        if &prefix[..MAGIC.len()] != MAGIC {
+            return Err(KvError::Decryption);
+        }
+        if prefix[MAGIC.len()] != VERSION {
+            return Err(KvError::Corrupted(DecodeError::UnsupportedFileVersion(prefix[MAGIC.len()])));
+        }
+
+        let (nonce, sealed) = rest.split_at(NONCE_BYTES);
+        let cipher = ChaCha20Poly1305::new(key.into());
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: prefix })
+            .map_err(|_| KvError::Decryption)?;
+
+        KvStore::decode_into(store, &plaintext)
+    }
+}
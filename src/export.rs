@@ -0,0 +1,59 @@
+//! Exporting whole stores to text formats.
+
+use std::io::Write;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::{Key, KvResult, KvStore, OwnedValue};
+
+#[cfg(feature = "serde")]
+mod json;
+
+// Quotes a CSV field per RFC 4180 if it contains a separator, quote or line break.
+fn write_csv_field<W: Write>(w: &mut W, field: &str) -> std::io::Result<()> {
+    if field.contains([',', '"', '\r', '\n']) {
+        write!(w, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        w.write_all(field.as_bytes())
+    }
+}
+
+impl KvStore {
+    /// Writes all entries as CSV with the header `key_type,key,value_type,value`.
+    ///
+    /// Rows are sorted by key and end in CRLF. Blobs are base64-encoded.
+    pub fn export_csv<W: Write>(&self, w: &mut W) -> KvResult<()> {
+        let mut keys: Vec<&Key> = self.keys().collect();
+        keys.sort();
+
+        w.write_all(b"key_type,key,value_type,value\r\n")?;
+
+        for key in keys {
+            let Some(value) = self.get_owned(key)? else {
+                continue;
+            };
+
+            let (key_type, key_text) = match key {
+                Key::Integer(i) => ("integer", i.to_string()),
+                Key::Text(s) => ("text", s.clone()),
+                Key::Interned(s) => ("text", s.to_string()),
+            };
+            let (value_type, value_text) = match value {
+                OwnedValue::Integer(i) => ("integer", i.to_string()),
+                OwnedValue::Bool(b) => ("bool", b.to_string()),
+                OwnedValue::Text(s) => ("text", s),
+                OwnedValue::Blob(b) => ("blob", STANDARD.encode(b)),
+                OwnedValue::Timestamp(t) => ("timestamp", crate::timestamp::format_iso8601(t)),
+            };
+
+            write!(w, "{key_type},")?;
+            write_csv_field(w, &key_text)?;
+            write!(w, ",{value_type},")?;
+            write_csv_field(w, &value_text)?;
+            w.write_all(b"\r\n")?;
+        }
+
+        Ok(())
+    }
+}
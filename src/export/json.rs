@@ -0,0 +1,124 @@
+//! JSON export and import (`serde` feature).
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Key, KvResult, KvStore, OwnedValue};
+
+// Export layout: `{"key": {"type": ..., "value": ...}, "value": {"type": ..., "value": ...}}`.
+// Kept separate from the derives on `Key`/`OwnedValue` so those stay compact for bincode.
+#[derive(Serialize, Deserialize)]
+struct JsonEntry {
+    key: JsonKey,
+    value: JsonValue,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+enum JsonKey {
+    Integer(i64),
+    Text(String),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+enum JsonValue {
+    Integer(i64),
+    Bool(bool),
+    Text(String),
+    Blob(#[serde(with = "crate::serde_blob")] Vec<u8>),
+    Timestamp(#[serde(with = "iso8601")] u64),
+}
+
+// Timestamps are exported as ISO-8601 strings (`2024-05-01T12:00:00Z`).
+mod iso8601 {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::timestamp::{format_iso8601, parse_iso8601};
+
+    pub fn serialize<S: Serializer>(secs: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_iso8601(*secs))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_iso8601(&s).ok_or_else(|| D::Error::custom(format!("invalid timestamp {s:?}")))
+    }
+}
+
+impl From<Key> for JsonKey {
+    fn from(key: Key) -> Self {
+        match key {
+            Key::Integer(i) => JsonKey::Integer(i),
+            Key::Text(s) => JsonKey::Text(s),
+            Key::Interned(s) => JsonKey::Text(s.to_string()),
+        }
+    }
+}
+
+impl From<JsonKey> for Key {
+    fn from(key: JsonKey) -> Self {
+        match key {
+            JsonKey::Integer(i) => Key::Integer(i),
+            JsonKey::Text(s) => Key::Text(s),
+        }
+    }
+}
+
+impl From<OwnedValue> for JsonValue {
+    fn from(value: OwnedValue) -> Self {
+        match value {
+            OwnedValue::Integer(i) => JsonValue::Integer(i),
+            OwnedValue::Bool(b) => JsonValue::Bool(b),
+            OwnedValue::Text(s) => JsonValue::Text(s),
+            OwnedValue::Blob(b) => JsonValue::Blob(b),
+            OwnedValue::Timestamp(t) => JsonValue::Timestamp(t),
+        }
+    }
+}
+
+impl From<JsonValue> for OwnedValue {
+    fn from(value: JsonValue) -> Self {
+        match value {
+            JsonValue::Integer(i) => OwnedValue::Integer(i),
+            JsonValue::Bool(b) => OwnedValue::Bool(b),
+            JsonValue::Text(s) => OwnedValue::Text(s),
+            JsonValue::Blob(b) => OwnedValue::Blob(b),
+            JsonValue::Timestamp(t) => OwnedValue::Timestamp(t),
+        }
+    }
+}
+
+impl KvStore {
+    /// Exports all entries as a JSON array of `{ "key": ..., "value": ... }` objects.
+    ///
+    /// Entries are sorted by key, so equal stores always produce the same string.
+    /// Blobs are base64-encoded.
+    pub fn export_json(&self) -> KvResult<String> {
+        let mut keys: Vec<&Key> = self.keys().collect();
+        keys.sort();
+
+        let mut entries = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some(value) = self.get_owned(key)? {
+                entries.push(JsonEntry {
+                    key: key.clone().into(),
+                    value: value.into(),
+                });
+            }
+        }
+
+        Ok(serde_json::to_string(&entries)?)
+    }
+
+    /// Builds a store from the output of [`export_json`](KvStore::export_json).
+    /// If a key appears more than once, the last entry wins.
+    pub fn import_json(json: &str) -> KvResult<KvStore> {
+        let entries: Vec<JsonEntry> = serde_json::from_str(json)?;
+
+        let mut store = KvStore::new();
+        for entry in entries {
+            store.insert(entry.key.into(), entry.value.into())?;
+        }
+        Ok(store)
+    }
+}
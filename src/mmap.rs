@@ -0,0 +1,134 @@
+//! Read-only store backed by a memory-mapped file (`mmap` feature).
+
+use indexmap::IndexMap;
+use memmap2::Mmap;
+
+use crate::{
+    decode_entry, deserialize_header, deserialize_owned, read_borrowed, scan_records,
+    strip_file_header, BorrowedValue, Codec, Key, KvResult, KvStore, OwnedValue, StoreIter,
+    TypeTag,
+};
+
+/// A persisted store opened with [`KvStore::open_mmap`].
+///
+/// Only the index lives on the heap; values are decoded straight from the mapping, so
+/// borrowed text and blobs point into the file. Checksums are verified on every read,
+/// not when opening.
+pub struct MmapStore {
+    map: Mmap,
+    // Start of the first record, after the optional file header.
+    records_start: usize,
+    index: IndexMap<Key, usize>,
+    codec: Codec,
+}
+
+impl KvStore {
+    /// Maps the store file at `path` read-only instead of reading it into memory.
+    ///
+    /// Unlike [`KvStore::load_from_file`], a missing file is an error. The file must not
+    /// be modified while the returned store is alive.
+    pub fn open_mmap(path: &str) -> KvResult<MmapStore> {
+        let file = std::fs::File::open(path)?;
+
+        // SAFETY: the mapping is read-only and the caller guarantees that the file is
+        // not truncated or rewritten while it is mapped.
+        let map = unsafe { Mmap::map(&file)? };
+
+        let (records, codec) = strip_file_header(&map, Codec::default())?;
+        let records_start = map.len() - records.len();
+
+        let mut index = IndexMap::new();
+        scan_records(records, records_start, codec, |key, range| {
+            // Only tombstones are verified here (their payload is empty, so that is
+            // cheap); values are verified when read.
+            let entry = &records[range.clone()];
+            if deserialize_header(entry, codec)?.tag == TypeTag::Tombstone as u8 {
+                decode_entry(entry, codec)?;
+                index.shift_remove(&key);
+            } else {
+                index.insert(key, range.start);
+            }
+            Ok(())
+        })?;
+
+        Ok(MmapStore {
+            map,
+            records_start,
+            index,
+            codec,
+        })
+    }
+}
+
+impl MmapStore {
+    fn records(&self) -> &[u8] {
+        &self.map[self.records_start..]
+    }
+
+    pub fn get_borrowed(&self, key: &Key) -> KvResult<Option<BorrowedValue<'_>>> {
+        match self.index.get(key) {
+            Some(&off) => Ok(Some(read_borrowed(&self.records()[off..], self.codec)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_owned(&self, key: &Key) -> KvResult<Option<OwnedValue>> {
+        match self.index.get(key) {
+            Some(&off) => Ok(Some(deserialize_owned(&self.records()[off..], self.codec)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Same semantics as [`KvStore::iter`]: entries failing to decode are skipped.
+    pub fn iter(&self) -> StoreIter<'_> {
+        StoreIter {
+            index_iter: self.index.iter(),
+            buf: self.records(),
+            codec: self.codec,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Key> + '_ {
+        self.index.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stats_alloc::Region;
+
+    #[test]
+    fn iterating_mapped_blobs_does_not_allocate() {
+        let path = "test_mmap_alloc.bin";
+        let mut kv = KvStore::new();
+        kv.insert(Key::Text("a".into()), OwnedValue::Blob(vec![1; 64])).unwrap();
+        kv.insert(Key::Text("b".into()), OwnedValue::Blob(vec![2; 4096])).unwrap();
+        kv.persist_to_file(path).unwrap();
+
+        let store = KvStore::open_mmap(path).unwrap();
+        let reg = Region::new(crate::GLOBAL);
+
+        let mut total = 0usize;
+        for entry in store.iter() {
+            if let BorrowedValue::Blob(bytes) = entry.value {
+                total += bytes.iter().map(|&b| b as usize).sum::<usize>();
+            }
+        }
+
+        let stats = reg.change();
+        drop(store);
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(total, 64 + 2 * 4096);
+        assert_eq!(stats.allocations, 0);
+    }
+}
@@ -0,0 +1,72 @@
+//! Several logical maps in one store, separated by key prefixes.
+
+use crate::{BorrowedValue, Key, KvResult, KvStore, OwnedValue};
+
+const SEPARATOR: char = ':';
+
+/// A view on the text keys of a [`KvStore`] that start with `"<name>:"`, obtained via
+/// [`KvStore::namespace`].
+///
+/// Keys are given and returned without the prefix, so namespaces with the same logical
+/// key names do not see each other. Integer keys and text keys outside the namespace
+/// are never touched.
+pub struct Namespace<'a> {
+    store: &'a mut KvStore,
+    prefix: String,
+}
+
+impl KvStore {
+    /// Opens the namespace `name`.
+    ///
+    /// # Panics
+    ///
+    /// If `name` contains `':'`, as `a` and `a:b` would otherwise share keys.
+    pub fn namespace(&mut self, name: &str) -> Namespace<'_> {
+        assert!(
+            !name.contains(SEPARATOR),
+            "namespace name {name:?} must not contain {SEPARATOR:?}"
+        );
+
+        Namespace {
+            store: self,
+            prefix: format!("{name}{SEPARATOR}"),
+        }
+    }
+}
+
+impl<'a> Namespace<'a> {
+    fn full_key(&self, key: &str) -> Key {
+        Key::Text(format!("{}{key}", self.prefix))
+    }
+
+    pub fn get(&self, key: &str) -> KvResult<Option<BorrowedValue<'_>>> {
+        self.store.get_borrowed(&self.full_key(key))
+    }
+
+    pub fn get_owned(&self, key: &str) -> KvResult<Option<OwnedValue>> {
+        self.store.get_owned(&self.full_key(key))
+    }
+
+    pub fn insert(&mut self, key: &str, value: OwnedValue) -> KvResult<Option<OwnedValue>> {
+        let key = self.full_key(key);
+        self.store.insert(key, value)
+    }
+
+    pub fn delete(&mut self, key: &str) -> bool {
+        let key = self.full_key(key);
+        self.store.delete(&key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.store.contains_key(&self.full_key(key))
+    }
+
+    /// Entries of this namespace in storage order, keys without the prefix. Skips
+    /// entries like [`KvStore::iter`] does.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, BorrowedValue<'_>)> + '_ {
+        self.store.iter().filter_map(move |entry| {
+            let s = entry.key.as_text()?;
+            Some((s.strip_prefix(self.prefix.as_str())?, entry.value))
+        })
+    }
+}
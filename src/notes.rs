@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize)]
 pub struct Note {
@@ -7,9 +8,38 @@ pub struct Note {
     pub body: String,
     pub tags: Vec<String>,
     pub updated_at: u64,
+    pub links: Vec<u64>,
 }
 
-#[derive(Clone)]
+// Layout of notes written before `links` existed. Those blobs carry no version marker.
+#[derive(Deserialize)]
+struct LegacyNote {
+    id: u64,
+    title: String,
+    body: String,
+    tags: Vec<String>,
+    updated_at: u64,
+}
+
+impl From<LegacyNote> for Note {
+    fn from(old: LegacyNote) -> Self {
+        Note {
+            id: old.id,
+            title: old.title,
+            body: old.body,
+            tags: old.tags,
+            updated_at: old.updated_at,
+            links: Vec::new(),
+        }
+    }
+}
+
+const NOTE_FORMAT_V1: &[u8; 8] = b"K9NOTE01";
+
+/// Title of notes imported from markdown without a `# ` heading.
+pub const MARKDOWN_DEFAULT_TITLE: &str = "Untitled";
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct NoteMeta {
     pub id: u64,
     pub title: String,
@@ -17,8 +47,29 @@ pub struct NoteMeta {
     pub tags: Vec<String>,
 }
 
+/// Order of [`NoteStore::list_meta_sorted`]. Ties are always broken by id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoteSort {
+    #[default]
+    ById,
+    /// Most recently updated first.
+    ByUpdatedDesc,
+    /// Alphabetical, ignoring case.
+    ByTitle,
+}
+
 pub struct NoteStore {
     kv: crate::KvStore,
+    // Lowercased title -> id, only maintained while unique titles are enforced.
+    titles: Option<HashMap<String, u64>>,
+    next_id: u64,
+    // File the store was opened from; consulted for ids taken by other instances.
+    path: Option<String>,
+    // Persist to `path` after every mutation and on drop.
+    autosave: bool,
+    // Ids deleted through this store. Saving to `path` keeps notes other stores wrote
+    // there meanwhile, except for these.
+    deleted: Vec<u64>,
 }
 
 impl NoteStore {
@@ -30,11 +81,196 @@ impl NoteStore {
             }
             Err(e) => return Err(e),
         };
-        Ok(NoteStore { kv })
+        let mut store = Self::from_kv(kv)?;
+        store.path = Some(path.to_string());
+        Ok(store)
+    }
+
+    /// Like [`open`](NoteStore::open), but new notes get ids of at least `start_id`.
+    /// Existing notes and the stored counter can still push ids higher.
+    pub fn open_with_start_id(path: &str, start_id: u64) -> crate::KvResult<NoteStore> {
+        let mut store = Self::open(path)?;
+        store.next_id = store.next_id.max(start_id);
+        Ok(store)
+    }
+
+    /// Like [`open`](NoteStore::open), but the store writes itself back to `path`
+    /// after every `create`, `update` and `delete`, and once more when dropped.
+    ///
+    /// Each save rewrites the whole file atomically, so a mutation costs time
+    /// proportional to the store size. For bulk changes, prefer [`open`](NoteStore::open)
+    /// and a single [`save`](NoteStore::save). Errors of the final save on drop are ignored.
+    pub fn open_autosave(path: &str) -> crate::KvResult<NoteStore> {
+        let mut store = Self::open(path)?;
+        store.autosave = true;
+        Ok(store)
+    }
+
+    fn autosave(&mut self) -> crate::KvResult<()> {
+        if self.autosave {
+            self.write_back()?;
+        }
+        Ok(())
+    }
+
+    // Atomically writes the store back to the file it was opened from, if any.
+    fn write_back(&self) -> crate::KvResult<()> {
+        if let Some(path) = &self.path {
+            self.merged_with_origin(path)?.persist_to_file_atomic(path)?;
+        }
+        Ok(())
+    }
+
+    // The store as it should be written to `path`. For the file the store was opened
+    // from, notes that other stores saved there meanwhile are kept; notes present in
+    // both are taken from this store.
+    fn merged_with_origin(&self, path: &str) -> crate::KvResult<std::borrow::Cow<'_, crate::KvStore>> {
+        use std::borrow::Cow;
+
+        if self.path.as_deref() != Some(path) {
+            return Ok(Cow::Borrowed(&self.kv));
+        }
+        let mut on_disk = crate::KvStore::load_from_file(path)?;
+        migrate_legacy_keys(&mut on_disk)?;
+
+        let disk_counter = match on_disk.get_owned(&next_id_key())? {
+            Some(crate::OwnedValue::Integer(i)) => i,
+            _ => 0,
+        };
+        for &id in &self.deleted {
+            on_disk.delete(&crate::Key::Integer(id as i64));
+            on_disk.delete(&meta_key(id));
+        }
+        on_disk.merge(self.kv.clone(), crate::MergePolicy::Overwrite)?;
+
+        let counter = (self.next_id.min(MAX_NOTE_ID) as i64).max(disk_counter);
+        on_disk.set(next_id_key(), crate::OwnedValue::Integer(counter))?;
+        Ok(Cow::Owned(on_disk))
+    }
+
+    // The persisted counter alone is not trusted: if it lags behind the stored notes
+    // (e.g. a file written by an older or foreign writer), new ids would overwrite
+    // existing notes. The next id is therefore at least one past the highest note id.
+    fn from_kv(mut kv: crate::KvStore) -> crate::KvResult<NoteStore> {
+        migrate_legacy_keys(&mut kv)?;
+
+        let counter = match kv.get_owned(&next_id_key())? {
+            Some(crate::OwnedValue::Integer(i)) => i.max(0) as u64,
+            Some(other) => {
+                return Err(crate::KvError::TypeMismatch {
+                    expected: crate::ValueType::Integer,
+                    found: other.value_type(),
+                })
+            }
+            None => 1,
+        };
+        let max_id = max_note_id(&kv);
+
+        Ok(NoteStore {
+            kv,
+            titles: None,
+            next_id: counter.max(max_id + 1),
+            path: None,
+            autosave: false,
+            deleted: Vec::new(),
+        })
+    }
+
+    // The id is derived when the note is created, not taken from the counter alone:
+    // another store opened from the same file may have saved notes since this one was
+    // opened. The counter only ensures that ids of deleted notes are not handed out again.
+    fn allocate_id(&self) -> crate::KvResult<u64> {
+        let mut id = self.next_id.max(max_note_id(&self.kv) + 1);
+        if let Some(path) = &self.path {
+            id = id.max(max_note_id(&crate::KvStore::load_from_file(path)?) + 1);
+        }
+
+        // Notes are stored under `Key::Integer`, so ids end at i64::MAX.
+        if id > MAX_NOTE_ID {
+            return Err(crate::KvError::IdSpaceExhausted);
+        }
+        Ok(id)
+    }
+
+    // Cannot overflow: `id` is at most MAX_NOTE_ID. A counter past the last id is
+    // stored as i64::MAX, which still makes the next create fail.
+    fn advance_counter(&mut self, id: u64) -> crate::KvResult<()> {
+        self.next_id = id + 1;
+        let next_meta = crate::OwnedValue::Integer(self.next_id.min(MAX_NOTE_ID) as i64);
+        self.kv.set(next_id_key(), next_meta)?;
+        Ok(())
+    }
+
+    /// Enables or disables case-insensitive title uniqueness for `create` and `update`.
+    ///
+    /// Enabling it scans all notes once to build the title index.
+    pub fn with_unique_titles(mut self, enabled: bool) -> crate::KvResult<NoteStore> {
+        self.titles = if enabled {
+            let mut titles = HashMap::new();
+            for note in self.all_notes()? {
+                titles.entry(note.title.to_lowercase()).or_insert(note.id);
+            }
+            Some(titles)
+        } else {
+            None
+        };
+        Ok(self)
+    }
+
+    fn check_title(&self, title: &str, id: Option<u64>) -> crate::KvResult<()> {
+        if let Some(titles) = &self.titles {
+            match titles.get(&title.to_lowercase()) {
+                Some(&owner) if Some(owner) != id => {
+                    return Err(crate::KvError::DuplicateTitle(title.to_string()));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn index_title(&mut self, title: &str, id: u64) {
+        if let Some(titles) = &mut self.titles {
+            titles.retain(|_, owner| *owner != id);
+            titles.insert(title.to_lowercase(), id);
+        }
     }
 
+    /// Writes the store to `path`.
+    ///
+    /// If `path` is the file the store was opened from, notes other stores saved there
+    /// since are kept, unless this store deleted them. A note saved by both stores is
+    /// written as this store has it. Two stores that create notes before either saves
+    /// can still hand out the same id; the store saving last wins that id.
     pub fn save(&self, path: &str) -> crate::KvResult<()> {
-        self.kv.persist_to_file(path)
+        self.merged_with_origin(path)?.persist_to_file(path)
+    }
+
+    /// Drops the bytes of overwritten and deleted notes from the underlying store and
+    /// writes it back to the file it was opened from.
+    ///
+    /// Notes, ids and links are unchanged, so this is safe to call periodically, e.g.
+    /// after many edits. The file is rewritten atomically.
+    pub fn compact(&mut self) -> crate::KvResult<()> {
+        self.kv.compact()?;
+        self.write_back()
+    }
+
+    /// Writes the current state, unsaved changes included, to `dest` atomically.
+    pub fn backup(&self, dest: &str) -> crate::KvResult<()> {
+        self.kv.persist_to_file_atomic(dest)
+    }
+
+    /// Checks the backup at `src`, atomically copies it to `dest` and opens it.
+    ///
+    /// Unlike [`open`](NoteStore::open), a missing `src` is an error.
+    pub fn restore(src: &str, dest: &str) -> crate::KvResult<NoteStore> {
+        let bytes = std::fs::read(src)?;
+        let kv = crate::KvStore::from_bytes(&bytes)?;
+        kv.persist_to_file_atomic(dest)?;
+        let mut store = Self::from_kv(kv)?;
+        store.path = Some(dest.to_string());
+        Ok(store)
     }
 
     pub fn get(&self, id: u64) -> crate::KvResult<Option<Note>> {
@@ -44,79 +280,452 @@ impl NoteStore {
                 let note = note_from_bytes(bytes)?;
                 Ok(Some(note))
             }
-            Some(_) => Err(crate::KvError::InvalidKeyType),
+            Some(other) => Err(not_a_blob(&other)),
             None => Ok(None),
         }
     }
 
     pub fn create(&mut self, title: String, body: String) -> crate::KvResult<u64> {
-        let meta_key = crate::Key::Text("__meta_next_id".to_string());
-        
-        let next_id = match self.kv.get_owned(&meta_key)? {
-            Some(crate::OwnedValue::Integer(i)) => i as u64,
-            Some(_) => return Err(crate::KvError::InvalidKeyType),
-            None => 1,
-        };
-        
-        let id = next_id;
+        self.create_with_tags(title, body, Vec::new())
+    }
+
+    /// Like [`create`](NoteStore::create), with tags. Tags are trimmed; empty tags and
+    /// repeats are dropped, the first occurrence keeps its position.
+    pub fn create_with_tags(
+        &mut self,
+        title: String,
+        body: String,
+        tags: Vec<String>,
+    ) -> crate::KvResult<u64> {
+        self.check_title(&title, None)?;
+
+        let id = self.allocate_id()?;
         let note = Note {
             id,
             title,
             body,
-            tags: vec![],
-            updated_at: 0,
+            tags: normalize_tags(tags),
+            updated_at: now_unix(),
+            links: vec![],
         };
         
-        let note_key = crate::Key::Integer(id as i64);
-        let note_value = crate::OwnedValue::Blob(note_to_bytes(&note));
-        self.kv.insert(note_key, note_value);
-        
-        let next_meta = crate::OwnedValue::Integer((next_id + 1) as i64);
-        self.kv.insert(meta_key, next_meta);
-        
+        self.write_note(&note)?;
+        self.advance_counter(id)?;
+
+        self.index_title(&note.title, id);
+        self.autosave()?;
+
         Ok(id)
     }
 
-    pub fn update(&mut self, note: Note) -> crate::KvResult<()> {
-        let key = crate::Key::Integer(note.id as i64);
-        let value = crate::OwnedValue::Blob(note_to_bytes(&note));
-        self.kv.insert(key, value);
+    pub fn update(&mut self, mut note: Note) -> crate::KvResult<()> {
+        self.check_title(&note.title, Some(note.id))?;
+        note.updated_at = now_unix();
+
+        self.write_note(&note)?;
+
+        self.index_title(&note.title, note.id);
+        self.autosave()
+    }
+
+    /// Changes the title of note `id` and returns whether the note existed.
+    ///
+    /// Goes through [`update`](NoteStore::update), so title uniqueness is enforced and
+    /// `updated_at` is bumped.
+    pub fn rename(&mut self, id: u64, new_title: String) -> crate::KvResult<bool> {
+        let Some(mut note) = self.get(id)? else {
+            return Ok(false);
+        };
+        note.title = new_title;
+        self.update(note)?;
+        Ok(true)
+    }
+
+    /// Deletes a note and returns whether it existed.
+    pub fn delete(&mut self, id: u64) -> crate::KvResult<bool> {
+        let existed = self.kv.delete(&crate::Key::Integer(id as i64));
+        self.kv.delete(&meta_key(id));
+        if let Some(titles) = &mut self.titles {
+            titles.retain(|_, owner| *owner != id);
+        }
+        if existed {
+            self.deleted.push(id);
+            self.autosave()?;
+        }
+        Ok(existed)
+    }
+
+    /// Deletes a note and removes it from the `links` of every other note.
+    pub fn delete_and_unlink(&mut self, id: u64) -> crate::KvResult<()> {
+        if !self.delete(id)? {
+            return Ok(());
+        }
+
+        for mut note in self.all_notes()? {
+            if note.links.contains(&id) {
+                note.links.retain(|&l| l != id);
+                self.update(note)?;
+            }
+        }
         Ok(())
     }
 
-    pub fn delete(&mut self, id: u64) -> crate::KvResult<()> {
-        self.kv.delete(&crate::Key::Integer(id as i64));
+    /// Records a link from note `from` to note `to`. Both notes must exist.
+    pub fn add_link(&mut self, from: u64, to: u64) -> crate::KvResult<()> {
+        if self.get(to)?.is_none() {
+            return Err(crate::KvError::NoteNotFound(to));
+        }
+        let mut note = self.get(from)?.ok_or(crate::KvError::NoteNotFound(from))?;
+
+        if !note.links.contains(&to) {
+            note.links.push(to);
+            self.update(note)?;
+        }
         Ok(())
     }
 
-    pub fn list_meta(&self) -> crate::KvResult<Vec<NoteMeta>> {
-        let mut metas = Vec::new();
-        
+    pub fn links_of(&self, id: u64) -> crate::KvResult<Vec<u64>> {
+        let note = self.get(id)?.ok_or(crate::KvError::NoteNotFound(id))?;
+        Ok(note.links)
+    }
+
+    /// Ids of all notes linking to `id`, sorted. Computed by scanning every note.
+    pub fn backlinks_of(&self, id: u64) -> crate::KvResult<Vec<u64>> {
+        let mut ids: Vec<u64> = self
+            .all_notes()?
+            .into_iter()
+            .filter(|note| note.links.contains(&id))
+            .map(|note| note.id)
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Notes whose title, tags or body contain `query` (ignoring case), sorted by id.
+    ///
+    /// Unlike the listings this decodes every note, bodies included.
+    pub fn search(&self, query: &str) -> crate::KvResult<Vec<NoteMeta>> {
+        let query = query.to_lowercase();
+
+        let mut metas: Vec<NoteMeta> = self
+            .all_notes()?
+            .into_iter()
+            .filter(|note| {
+                note.title.to_lowercase().contains(&query)
+                    || note.tags.iter().any(|t| t.to_lowercase().contains(&query))
+                    || note.body.to_lowercase().contains(&query)
+            })
+            .map(|note| NoteMeta {
+                id: note.id,
+                title: note.title,
+                updated_at: note.updated_at,
+                tags: note.tags,
+            })
+            .collect();
+
+        metas.sort_by_key(|m| m.id);
+        Ok(metas)
+    }
+
+    // Writes the note and its separate metadata record, which lets listings skip bodies.
+    fn write_note(&mut self, note: &Note) -> crate::KvResult<()> {
+        let meta = NoteMeta {
+            id: note.id,
+            title: note.title.clone(),
+            updated_at: note.updated_at,
+            tags: note.tags.clone(),
+        };
+        let meta_bytes = bincode::serialize(&meta).expect("Failed to serialize note meta");
+
+        self.kv.set(
+            crate::Key::Integer(note.id as i64),
+            crate::OwnedValue::Blob(note_to_bytes(note)),
+        )?;
+        self.kv.set(meta_key(note.id), crate::OwnedValue::Blob(meta_bytes))?;
+        Ok(())
+    }
+
+    /// Metadata of a single note, without decoding its body.
+    ///
+    /// Notes written before metadata records existed fall back to decoding the note.
+    pub fn get_meta(&self, id: u64) -> crate::KvResult<Option<NoteMeta>> {
+        match self.kv.get_borrowed(&meta_key(id))? {
+            Some(crate::BorrowedValue::Blob(bytes)) => bincode::deserialize(bytes)
+                .map(Some)
+                .map_err(note_decode_error),
+            Some(other) => Err(not_a_blob(&other)),
+            None => Ok(self.get(id)?.map(|note| NoteMeta {
+                id: note.id,
+                title: note.title,
+                updated_at: note.updated_at,
+                tags: note.tags,
+            })),
+        }
+    }
+
+    fn all_notes(&self) -> crate::KvResult<Vec<Note>> {
+        let mut notes = Vec::new();
         for entry in self.kv.iter() {
             if let crate::Key::Integer(_) = entry.key {
                 if let crate::BorrowedValue::Blob(bytes) = entry.value {
-                    let note = note_from_bytes(bytes)?;
-                    metas.push(NoteMeta {
-                        id: note.id,
-                        title: note.title,
-                        updated_at: note.updated_at,
-                        tags: note.tags,
-                    });
+                    notes.push(note_from_bytes(bytes)?);
                 } else {
-                    return Err(crate::KvError::InvalidKeyType);
+                    return Err(not_a_blob(&entry.value));
                 }
             }
         }
-        
+        Ok(notes)
+    }
+
+    pub fn list_meta(&self) -> crate::KvResult<Vec<NoteMeta>> {
+        let mut metas = Vec::new();
+
+        for key in self.kv.keys() {
+            if let crate::Key::Integer(id) = *key {
+                if let Some(meta) = self.get_meta(id as u64)? {
+                    metas.push(meta);
+                }
+            }
+        }
+
         metas.sort_by_key(|m| m.id);
         Ok(metas)
     }
+
+    /// All notes as a JSON array, sorted by id. Internal bookkeeping is not exported.
+    #[cfg(feature = "serde")]
+    pub fn export_json(&self) -> crate::KvResult<String> {
+        let mut notes = self.all_notes()?;
+        notes.sort_by_key(|note| note.id);
+        Ok(serde_json::to_string(&notes)?)
+    }
+
+    /// Like [`list_meta`](NoteStore::list_meta), in the given order.
+    pub fn list_meta_sorted(&self, order: NoteSort) -> crate::KvResult<Vec<NoteMeta>> {
+        // list_meta sorts by id and the sorts below are stable, which breaks ties by id.
+        let mut metas = self.list_meta()?;
+        match order {
+            NoteSort::ById => {}
+            NoteSort::ByUpdatedDesc => metas.sort_by_key(|m| std::cmp::Reverse(m.updated_at)),
+            NoteSort::ByTitle => metas.sort_by_cached_key(|m| m.title.to_lowercase()),
+        }
+        Ok(metas)
+    }
+
+    /// Number of notes. Only looks at keys, nothing is decoded.
+    pub fn count(&self) -> usize {
+        self.kv
+            .keys()
+            .filter(|key| matches!(key, crate::Key::Integer(_)))
+            .count()
+    }
+
+    /// Up to `limit` entries of [`list_meta`](NoteStore::list_meta), starting at `offset`.
+    /// Only the metadata of the returned page is decoded.
+    pub fn list_meta_paged(&self, offset: usize, limit: usize) -> crate::KvResult<Vec<NoteMeta>> {
+        let mut ids: Vec<u64> = self
+            .kv
+            .keys()
+            .filter_map(|key| match *key {
+                crate::Key::Integer(id) => Some(id as u64),
+                _ => None,
+            })
+            .collect();
+        ids.sort_unstable();
+
+        let mut metas = Vec::new();
+        for id in ids.into_iter().skip(offset).take(limit) {
+            if let Some(meta) = self.get_meta(id)? {
+                metas.push(meta);
+            }
+        }
+        Ok(metas)
+    }
+
+    /// Notes carrying `tag` (exact match, ignoring case), sorted by id.
+    pub fn find_by_tag(&self, tag: &str) -> crate::KvResult<Vec<NoteMeta>> {
+        let tag = tag.to_lowercase();
+        let mut metas = self.list_meta()?;
+        metas.retain(|m| m.tags.iter().any(|t| t.to_lowercase() == tag));
+        Ok(metas)
+    }
+
+    /// The note as markdown: `# {title}`, a blank line, the body and, if the note has
+    /// tags, a final `Tags: a, b` line.
+    pub fn export_markdown(&self, id: u64) -> crate::KvResult<Option<String>> {
+        let Some(note) = self.get(id)? else {
+            return Ok(None);
+        };
+
+        let mut out = format!("# {}\n\n{}\n", note.title, note.body);
+        if !note.tags.is_empty() {
+            out.push_str(&format!("\nTags: {}\n", note.tags.join(", ")));
+        }
+        Ok(Some(out))
+    }
+
+    /// Creates a note from markdown as written by [`export_markdown`](NoteStore::export_markdown).
+    ///
+    /// The first non-empty line is the title if it is a `# ` heading, otherwise the
+    /// note is titled [`MARKDOWN_DEFAULT_TITLE`] and the whole text is the body. A last
+    /// line starting with `Tags:` is read as a comma-separated tag list.
+    pub fn import_markdown(&mut self, text: &str) -> crate::KvResult<u64> {
+        let trimmed = text.trim_start();
+        let (title, rest) = match trimmed.strip_prefix("# ") {
+            Some(heading) => {
+                let (line, rest) = heading.split_once('\n').unwrap_or((heading, ""));
+                (line.trim().to_string(), rest)
+            }
+            None => (MARKDOWN_DEFAULT_TITLE.to_string(), text),
+        };
+
+        let rest = rest.trim_end();
+        let (body, tags) = match rest.rsplit_once('\n').unwrap_or(("", rest)) {
+            (body, last) if last.starts_with("Tags:") => {
+                let tags = last["Tags:".len()..].split(',').map(str::to_string).collect();
+                (body, tags)
+            }
+            _ => (rest, Vec::new()),
+        };
+
+        let body = body.trim_matches(|c| c == '\n' || c == '\r').to_string();
+        self.create_with_tags(title, body, tags)
+    }
+
+    /// Every tag used by at least one note, sorted and without duplicates. Reads the
+    /// metadata records, note bodies are only decoded for notes without one.
+    pub fn all_tags(&self) -> crate::KvResult<Vec<String>> {
+        let tags: std::collections::BTreeSet<String> = self
+            .list_meta()?
+            .into_iter()
+            .flat_map(|meta| meta.tags)
+            .collect();
+        Ok(tags.into_iter().collect())
+    }
+}
+
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !out.iter().any(|t| t == tag) {
+            out.push(tag.to_string());
+        }
+    }
+    out
+}
+
+const MAX_NOTE_ID: u64 = i64::MAX as u64;
+
+fn max_note_id(kv: &crate::KvStore) -> u64 {
+    kv.keys()
+        .filter_map(|key| match *key {
+            crate::Key::Integer(id) if id >= 0 => Some(id as u64),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// Bookkeeping lives in this reserved namespace, notes themselves under integer keys.
+const RESERVED_PREFIX: &str = "__notes:";
+
+// Key names used for bookkeeping before the reserved namespace existed.
+const LEGACY_NEXT_ID_KEY: &str = "__meta_next_id";
+const LEGACY_META_PREFIX: &str = "__meta_note:";
+
+fn next_id_key() -> crate::Key {
+    crate::Key::Text(format!("{RESERVED_PREFIX}next_id"))
+}
+
+fn meta_key(id: u64) -> crate::Key {
+    crate::Key::Text(format!("{RESERVED_PREFIX}meta:{id}"))
+}
+
+// Moves bookkeeping of older files into the reserved namespace. Unknown text keys are
+// left alone.
+fn migrate_legacy_keys(kv: &mut crate::KvStore) -> crate::KvResult<()> {
+    let legacy: Vec<(crate::Key, crate::Key)> = kv
+        .keys()
+        .filter_map(|key| {
+            let name = key.as_text()?;
+            let new_key = if name == LEGACY_NEXT_ID_KEY {
+                next_id_key()
+            } else {
+                meta_key(name.strip_prefix(LEGACY_META_PREFIX)?.parse().ok()?)
+            };
+            Some((key.clone(), new_key))
+        })
+        .collect();
+
+    for (old_key, new_key) in legacy {
+        if let Some(value) = kv.get_owned(&old_key)? {
+            kv.insert(new_key, value)?;
+        }
+        kv.delete(&old_key);
+    }
+    Ok(())
+}
+
+// Seconds since the Unix epoch; a clock set before 1970 counts as 0.
+fn now_unix() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Drop for NoteStore {
+    fn drop(&mut self) {
+        let _ = self.autosave();
+    }
 }
 
 pub fn note_to_bytes(note: &Note) -> Vec<u8> {
-    bincode::serialize(note).expect("Failed to serialize note")
+    let mut bytes = NOTE_FORMAT_V1.to_vec();
+    bincode::serialize_into(&mut bytes, note).expect("Failed to serialize note");
+    bytes
+}
+
+// Notes and their metadata records are always stored as blobs.
+fn not_a_blob(found: &crate::BorrowedValue) -> crate::KvError {
+    crate::KvError::TypeMismatch {
+        expected: crate::ValueType::Blob,
+        found: found.value_type(),
+    }
+}
+
+fn note_decode_error(e: bincode::Error) -> crate::KvError {
+    crate::KvError::Corrupted(crate::DecodeError::NoteDecode(e.to_string()))
 }
 
 pub fn note_from_bytes(bytes: &[u8]) -> Result<Note, crate::KvError> {
-    bincode::deserialize(bytes).map_err(|_| crate::KvError::Corrupted(crate::DecodeError::NoteDecodeFailed))
+    let note = match bytes.strip_prefix(NOTE_FORMAT_V1) {
+        Some(rest) => bincode::deserialize(rest),
+        None => bincode::deserialize::<LegacyNote>(bytes).map(Note::from),
+    };
+    note.map_err(note_decode_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stats_alloc::Region;
+
+    #[test]
+    fn list_meta_does_not_decode_bodies() {
+        let mut store = NoteStore::from_kv(crate::KvStore::new()).unwrap();
+        let body = "x".repeat(4 * 1024 * 1024);
+        store.create("Gross".to_string(), body.clone()).unwrap();
+        store.create("Klein".to_string(), "y".to_string()).unwrap();
+
+        let reg = Region::new(crate::GLOBAL);
+        let metas = store.list_meta().unwrap();
+        let stats = reg.change();
+
+        assert_eq!(metas.len(), 2);
+        assert_eq!(metas[0].title, "Gross");
+        // Decoding the body alone would allocate its full size
+        assert!(stats.bytes_allocated < body.len() / 4);
+    }
 }
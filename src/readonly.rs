@@ -0,0 +1,59 @@
+//! Read-only access to a loaded store.
+
+use crate::{BorrowedValue, Key, KvResult, KvStore, OwnedValue, StoreIter};
+
+/// A loaded store without any write methods, obtained via
+/// [`KvStore::load_from_file_readonly`].
+///
+/// Nothing can be inserted or deleted through it:
+///
+/// ```compile_fail
+/// use kv_store::{Key, KvStore, OwnedValue};
+///
+/// let mut store = KvStore::load_from_file_readonly("missing.bin").unwrap();
+/// store.insert(Key::Integer(1), OwnedValue::Bool(true));
+/// ```
+pub struct ReadOnlyStore {
+    store: KvStore,
+}
+
+impl KvStore {
+    /// Loads `path` like [`load_from_file`](KvStore::load_from_file), but returns a
+    /// store that can only be read.
+    pub fn load_from_file_readonly(path: &str) -> KvResult<ReadOnlyStore> {
+        Ok(ReadOnlyStore {
+            store: KvStore::load_from_file(path)?,
+        })
+    }
+}
+
+impl ReadOnlyStore {
+    pub fn get_borrowed(&self, key: &Key) -> KvResult<Option<BorrowedValue<'_>>> {
+        self.store.get_borrowed(key)
+    }
+
+    pub fn get_owned(&self, key: &Key) -> KvResult<Option<OwnedValue>> {
+        self.store.get_owned(key)
+    }
+
+    /// See [`KvStore::iter`].
+    pub fn iter(&self) -> StoreIter<'_> {
+        self.store.iter()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Key> + '_ {
+        self.store.keys()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = BorrowedValue<'_>> + '_ {
+        self.store.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.store.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.index.is_empty()
+    }
+}
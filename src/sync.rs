@@ -0,0 +1,59 @@
+//! Sharing a store between threads.
+
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{Key, KvResult, KvStore, OwnedValue};
+
+/// A [`KvStore`] behind a `RwLock`, for sharing via `Arc` between threads.
+///
+/// Reads take the read lock and run concurrently, writes take the write lock.
+/// Borrowed values cannot outlive the lock guard, so only owned accessors exist;
+/// use [`snapshot`](SyncKvStore::snapshot) for anything else.
+pub struct SyncKvStore {
+    inner: RwLock<KvStore>,
+}
+
+impl SyncKvStore {
+    pub fn new(store: KvStore) -> Self {
+        SyncKvStore {
+            inner: RwLock::new(store),
+        }
+    }
+
+    // A panic while holding the lock cannot leave a half-written entry visible (the
+    // index is only repointed after a value is complete), so poisoning is ignored.
+    fn read(&self) -> RwLockReadGuard<'_, KvStore> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, KvStore> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn get_owned(&self, key: &Key) -> KvResult<Option<OwnedValue>> {
+        self.read().get_owned(key)
+    }
+
+    pub fn insert(&self, key: Key, value: OwnedValue) -> KvResult<Option<OwnedValue>> {
+        self.write().insert(key, value)
+    }
+
+    pub fn delete(&self, key: &Key) -> bool {
+        self.write().delete(key)
+    }
+
+    /// Clones the current state under the read lock.
+    pub fn snapshot(&self) -> KvStore {
+        self.read().clone()
+    }
+
+    pub fn into_inner(self) -> KvStore {
+        self.inner.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<KvStore> for SyncKvStore {
+    fn from(store: KvStore) -> Self {
+        SyncKvStore::new(store)
+    }
+}
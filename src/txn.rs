@@ -0,0 +1,77 @@
+//! All-or-nothing batches of inserts and deletes.
+
+use crate::{deserialize_owned, serialize_value, Key, KvError, KvResult, KvStore, OwnedValue};
+
+/// Buffered writes of a [`KvStore::transaction`].
+///
+/// Inserts are validated and serialized when they are made, so a failing insert
+/// surfaces inside the transaction closure. Nothing reaches the store before the
+/// closure returns `Ok`.
+pub struct Txn<'a> {
+    store: &'a KvStore,
+    // Serialized value entry per write, `None` for a delete. Applied in order.
+    writes: Vec<(Key, Option<Vec<u8>>)>,
+}
+
+impl KvStore {
+    /// Runs `f` on a [`Txn`] and applies its inserts and deletes if `f` returns `Ok`.
+    ///
+    /// If `f` fails, for example because an insert was rejected by the validator or
+    /// the size limit, none of its writes are applied and the error is returned.
+    pub fn transaction<F>(&mut self, f: F) -> KvResult<()>
+    where
+        F: FnOnce(&mut Txn) -> KvResult<()>,
+    {
+        let mut txn = Txn {
+            store: self,
+            writes: Vec::new(),
+        };
+        f(&mut txn)?;
+        let writes = txn.writes;
+
+        // Everything was checked when it was buffered, so applying cannot fail halfway.
+        for (key, entry) in writes {
+            match entry {
+                Some(entry) => {
+                    let offset = self.data.len();
+                    self.data.extend_from_slice(&entry);
+                    self.place_appended(key, offset);
+                }
+                None => {
+                    self.delete(&key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Txn<'_> {
+    /// Buffers an insert. Runs the store's validator and size checks right away.
+    pub fn insert(&mut self, key: Key, value: OwnedValue) -> KvResult<()> {
+        if let Some(validator) = &self.store.validator {
+            validator(&key, &value).map_err(KvError::ValidationFailed)?;
+        }
+
+        let mut entry = Vec::new();
+        serialize_value(&value, self.store.codec, &mut entry);
+        self.store.codec.check_value_size(entry.len())?;
+
+        self.writes.push((key, Some(entry)));
+        Ok(())
+    }
+
+    /// Buffers a delete.
+    pub fn delete(&mut self, key: &Key) {
+        self.writes.push((key.clone(), None));
+    }
+
+    /// Reads `key` as it will be after commit: buffered writes first, then the store.
+    pub fn get_owned(&self, key: &Key) -> KvResult<Option<OwnedValue>> {
+        match self.writes.iter().rev().find(|(k, _)| k == key) {
+            Some((_, Some(entry))) => Ok(Some(deserialize_owned(entry, self.store.codec)?)),
+            Some((_, None)) => Ok(None),
+            None => self.store.get_owned(key),
+        }
+    }
+}
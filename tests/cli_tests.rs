@@ -0,0 +1,123 @@
+use std::fs;
+use std::process::{Command, Output};
+
+fn notes_cli(file: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_notes_cli"))
+        .arg(file)
+        .args(args)
+        .output()
+        .expect("Failed to run notes_cli")
+}
+
+fn stdout(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+#[test]
+fn test_cli_create_then_delete() {
+    let test_file = "test_cli_delete.bin";
+    let _ = fs::remove_file(test_file);
+
+    let out = notes_cli(test_file, &["new", "Titel", "Inhalt"]);
+    assert!(out.status.success());
+    assert_eq!(stdout(&out), "created 1\n");
+
+    let out = notes_cli(test_file, &["delete", "1"]);
+    assert!(out.status.success());
+    assert_eq!(stdout(&out), "deleted 1\n");
+
+    // Gelöscht bleibt gelöscht, auch nach erneutem Laden
+    assert_eq!(stdout(&notes_cli(test_file, &["show", "1"])), "not found\n");
+    assert_eq!(stdout(&notes_cli(test_file, &["delete", "1"])), "not found\n");
+    assert_eq!(stdout(&notes_cli(test_file, &["list"])), "");
+
+    let out = notes_cli(test_file, &["delete", "abc"]);
+    assert!(!out.status.success());
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_cli_edit() {
+    let test_file = "test_cli_edit.bin";
+    let _ = fs::remove_file(test_file);
+
+    notes_cli(test_file, &["new", "Alt", "alter Text"]);
+
+    let out = notes_cli(test_file, &["edit", "1", "Neu", "neuer Text"]);
+    assert!(out.status.success());
+    assert_eq!(stdout(&out), "updated 1\n");
+    assert_eq!(stdout(&notes_cli(test_file, &["show", "1"])), "Neu\nneuer Text\n");
+
+    // Unbekannte ID
+    let out = notes_cli(test_file, &["edit", "7", "x", "y"]);
+    assert!(!out.status.success());
+    assert_eq!(stdout(&out), "not found\n");
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_cli_list_json() {
+    let test_file = "test_cli_list_json.bin";
+    let _ = fs::remove_file(test_file);
+
+    notes_cli(test_file, &["new", "Erste", "a"]);
+    notes_cli(test_file, &["new", "Zweite \"mit\" Zitat", "b"]);
+
+    // Standardausgabe bleibt unverändert
+    assert_eq!(
+        stdout(&notes_cli(test_file, &["list"])),
+        "1  Erste\n2  Zweite \"mit\" Zitat\n"
+    );
+
+    let out = notes_cli(test_file, &["list", "--json"]);
+    assert!(out.status.success());
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(&out)).unwrap();
+    let notes = json.as_array().unwrap();
+    assert_eq!(notes.len(), 2);
+    assert_eq!(notes[0]["id"], 1);
+    assert_eq!(notes[0]["title"], "Erste");
+    assert_eq!(notes[1]["title"], "Zweite \"mit\" Zitat");
+    assert!(notes[1]["tags"].as_array().unwrap().is_empty());
+    assert!(notes[1]["updated_at"].as_u64().unwrap() > 0);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_cli_tag_add_and_rm() {
+    let test_file = "test_cli_tag.bin";
+    let _ = fs::remove_file(test_file);
+
+    notes_cli(test_file, &["new", "Getaggt", "x"]);
+
+    assert_eq!(stdout(&notes_cli(test_file, &["tag", "add", "1", "rust"])), "1  rust\n");
+    assert_eq!(stdout(&notes_cli(test_file, &["tag", "add", "1", "arbeit"])), "1  rust, arbeit\n");
+    let bytes = fs::read(test_file).unwrap();
+    let modified = fs::metadata(test_file).unwrap().modified().unwrap();
+    let meta = stdout(&notes_cli(test_file, &["list", "--json"]));
+
+    // Doppelter Tag wird nicht erneut angelegt
+    assert_eq!(stdout(&notes_cli(test_file, &["tag", "add", "1", " rust "])), "1  rust, arbeit\n");
+    // Entfernen eines nicht vorhandenen Tags ist ein No-op
+    let out = notes_cli(test_file, &["tag", "rm", "1", "fehlt"]);
+    assert!(out.status.success());
+    assert_eq!(stdout(&out), "1  rust, arbeit\n");
+
+    // Weder updated_at noch die Datei ändern sich
+    assert_eq!(stdout(&notes_cli(test_file, &["list", "--json"])), meta);
+    assert_eq!(fs::read(test_file).unwrap(), bytes);
+    assert_eq!(fs::metadata(test_file).unwrap().modified().unwrap(), modified);
+    assert_eq!(stdout(&notes_cli(test_file, &["tag", "rm", "1", "rust"])), "1  arbeit\n");
+
+    let json: serde_json::Value =
+        serde_json::from_str(&stdout(&notes_cli(test_file, &["list", "--json"]))).unwrap();
+    assert_eq!(json[0]["tags"], serde_json::json!(["arbeit"]));
+
+    assert!(!notes_cli(test_file, &["tag", "add", "9", "x"]).status.success());
+    assert!(!notes_cli(test_file, &["tag", "mv", "1", "x"]).status.success());
+
+    let _ = fs::remove_file(test_file);
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "encryption")]
+
+use kv_store::{Checksum, Key, KvError, KvStore, OwnedValue};
+use std::fs;
+
+const KEY: [u8; 32] = [7; 32];
+
+fn sample_store() -> KvStore {
+    let mut kv = KvStore::new();
+    kv.insert(Key::Text("geheim".into()), OwnedValue::Text("streng vertraulich".into())).unwrap();
+    kv.insert(Key::Integer(1), OwnedValue::Blob(vec![1, 2, 3])).unwrap();
+    kv
+}
+
+#[test]
+fn test_encrypted_roundtrip() {
+    let path = "test_encrypted_roundtrip.bin";
+    let kv = sample_store();
+    kv.persist_to_file_encrypted(path, &KEY).unwrap();
+
+    // Klartext taucht in der Datei nicht auf
+    let bytes = fs::read(path).unwrap();
+    assert!(bytes.starts_with(b"K9KE"));
+    assert!(!bytes.windows(6).any(|w| w == b"geheim"));
+
+    let loaded = KvStore::load_from_file_encrypted(path, &KEY).unwrap();
+    assert_eq!(loaded.diff(&kv).unwrap(), vec![]);
+
+    // Jedes Speichern nutzt eine neue Nonce
+    kv.persist_to_file_encrypted(path, &KEY).unwrap();
+    assert_ne!(fs::read(path).unwrap(), bytes);
+
+    // Die normalen Loader können die Datei nicht lesen
+    assert!(KvStore::load_from_file(path).is_err());
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_encrypted_roundtrip_with_seed_and_checksum() {
+    let path = "test_encrypted_codec.bin";
+
+    let mut seeded = KvStore::with_checksum_seed(3);
+    seeded.insert(Key::Text("a".into()), OwnedValue::Integer(1)).unwrap();
+    seeded.persist_to_file_encrypted(path, &KEY).unwrap();
+
+    let loaded = KvStore::load_from_file_encrypted_with_seed(path, &KEY, 3).unwrap();
+    assert_eq!(loaded.diff(&seeded).unwrap(), vec![]);
+    assert!(matches!(
+        KvStore::load_from_file_encrypted(path, &KEY),
+        Err(KvError::ChecksumSeedMismatch)
+    ));
+
+    let mut castagnoli = KvStore::with_checksum(Checksum::Crc32Castagnoli);
+    castagnoli.insert(Key::Integer(2), OwnedValue::Bool(true)).unwrap();
+    castagnoli.persist_to_file_encrypted(path, &KEY).unwrap();
+
+    let loaded =
+        KvStore::load_from_file_encrypted_with_checksum(path, &KEY, Checksum::Crc32Castagnoli).unwrap();
+    assert_eq!(loaded.diff(&castagnoli).unwrap(), vec![]);
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_encrypted_wrong_key_or_tampering_fails() {
+    let path = "test_encrypted_wrong_key.bin";
+    sample_store().persist_to_file_encrypted(path, &KEY).unwrap();
+
+    let mut wrong = KEY;
+    wrong[31] ^= 1;
+    assert!(matches!(KvStore::load_from_file_encrypted(path, &wrong), Err(KvError::Decryption)));
+
+    // Ein gekipptes Bit im Chiffretext
+    let mut bytes = fs::read(path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last - 20] ^= 0x01;
+    fs::write(path, &bytes).unwrap();
+    assert!(matches!(KvStore::load_from_file_encrypted(path, &KEY), Err(KvError::Decryption)));
+
+    // Unverschlüsselte Datei
+    sample_store().persist_to_file(path).unwrap();
+    assert!(matches!(KvStore::load_from_file_encrypted(path, &KEY), Err(KvError::Decryption)));
+
+    fs::write(path, b"K9KE\x01").unwrap();
+    assert!(matches!(KvStore::load_from_file_encrypted(path, &KEY), Err(KvError::UnexpectedEof { offset: 0 })));
+
+    let _ = fs::remove_file(path);
+}
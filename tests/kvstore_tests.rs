@@ -1,4 +1,4 @@
-use kv_store::{KvStore, Key, OwnedValue, BorrowedValue, BorrowedEntry};
+use kv_store::{Checksum, CorruptionKind, DecodeError, Endianness, KvStore, KvError, Key, Limits, OwnedValue, BorrowedValue, BorrowedEntry, Change, MergePolicy, read_entry, write_entry, PersistOptions, StoreStats, SyncKvStore, Txn, ValueType, verify_value_bytes};
 
 fn ktxt(s: &str) -> Key {
     Key::Text(s.to_string())
@@ -11,7 +11,7 @@ fn kint(i: i64) -> Key {
 #[test]
 fn insert_and_get_text() {
     let mut kv = KvStore::new();
-    kv.insert(ktxt("lang"), OwnedValue::Text("Rust".into()));
+    kv.insert(ktxt("lang"), OwnedValue::Text("Rust".into())).unwrap();
 
     // get_borrowed -> Result<Option<BorrowedValue>>
     assert_eq!(
@@ -23,9 +23,9 @@ fn insert_and_get_text() {
 #[test]
 fn insert_and_get_integer_bool_blob() {
     let mut kv = KvStore::new();
-    kv.insert(ktxt("answer"), OwnedValue::Integer(42));
-    kv.insert(ktxt("flag"), OwnedValue::Bool(true));
-    kv.insert(ktxt("raw"), OwnedValue::Blob(vec![9, 8, 7]));
+    kv.insert(ktxt("answer"), OwnedValue::Integer(42)).unwrap();
+    kv.insert(ktxt("flag"), OwnedValue::Bool(true)).unwrap();
+    kv.insert(ktxt("raw"), OwnedValue::Blob(vec![9, 8, 7])).unwrap();
 
     assert_eq!(
         kv.get_borrowed(&ktxt("answer")).unwrap(),
@@ -51,8 +51,8 @@ fn insert_and_get_integer_bool_blob() {
 #[test]
 fn overwrite_key_updates_value() {
     let mut kv = KvStore::new();
-    kv.insert(ktxt("k"), OwnedValue::Text("old".into()));
-    kv.insert(ktxt("k"), OwnedValue::Text("new".into()));
+    kv.insert(ktxt("k"), OwnedValue::Text("old".into())).unwrap();
+    kv.insert(ktxt("k"), OwnedValue::Text("new".into())).unwrap();
 
     assert_eq!(
         kv.get_borrowed(&ktxt("k")).unwrap(),
@@ -64,9 +64,9 @@ fn overwrite_key_updates_value() {
 fn iter_returns_entries_in_storage_order() {
     let mut kv = KvStore::new();
 
-    kv.insert(ktxt("a"), OwnedValue::Integer(10));
-    kv.insert(ktxt("b"), OwnedValue::Bool(true));
-    kv.insert(ktxt("c"), OwnedValue::Text("hello".into()));
+    kv.insert(ktxt("a"), OwnedValue::Integer(10)).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Bool(true)).unwrap();
+    kv.insert(ktxt("c"), OwnedValue::Text("hello".into())).unwrap();
 
     let items: Vec<(Key, BorrowedValue)> =
         kv.iter().map(|e| (e.key.clone(), e.value)).collect();
@@ -86,7 +86,7 @@ fn iter_returns_entries_in_storage_order() {
 #[test]
 fn iter_stops_correctly() {
     let mut kv = KvStore::new();
-    kv.insert(ktxt("x"), OwnedValue::Integer(1));
+    kv.insert(ktxt("x"), OwnedValue::Integer(1)).unwrap();
 
     let mut it = kv.iter();
 
@@ -102,9 +102,9 @@ fn iter_stops_correctly() {
 #[test]
 fn keys_are_returned_in_storage_order() {
     let mut kv = KvStore::new();
-    kv.insert(ktxt("first"), OwnedValue::Integer(1));
-    kv.insert(ktxt("second"), OwnedValue::Integer(2));
-    kv.insert(ktxt("third"), OwnedValue::Integer(3));
+    kv.insert(ktxt("first"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("second"), OwnedValue::Integer(2)).unwrap();
+    kv.insert(ktxt("third"), OwnedValue::Integer(3)).unwrap();
 
     let keys: Vec<&Key> = kv.keys().collect();
 
@@ -117,8 +117,8 @@ fn keys_are_returned_in_storage_order() {
 #[test]
 fn values_returned_in_storage_order() {
     let mut kv = KvStore::new();
-    kv.insert(kint(5), OwnedValue::Text("five".into()));
-    kv.insert(kint(6), OwnedValue::Text("six".into()));
+    kv.insert(kint(5), OwnedValue::Text("five".into())).unwrap();
+    kv.insert(kint(6), OwnedValue::Text("six".into())).unwrap();
 
     let vals: Vec<_> = kv.values().collect();
 
@@ -131,9 +131,9 @@ fn values_returned_in_storage_order() {
 fn iter_works_with_all_types() {
     let mut kv = KvStore::new();
 
-    kv.insert(kint(1), OwnedValue::Integer(42));
-    kv.insert(kint(2), OwnedValue::Bool(false));
-    kv.insert(kint(3), OwnedValue::Blob(vec![1, 2, 3]));
+    kv.insert(kint(1), OwnedValue::Integer(42)).unwrap();
+    kv.insert(kint(2), OwnedValue::Bool(false)).unwrap();
+    kv.insert(kint(3), OwnedValue::Blob(vec![1, 2, 3])).unwrap();
 
     let vals: Vec<_> = kv.iter().map(|e| e.value).collect();
 
@@ -152,8 +152,8 @@ fn persist_and_load_roundtrip() {
 
     {
         let mut kv = KvStore::new();
-        kv.insert(ktxt("a"), OwnedValue::Integer(1));
-        kv.insert(ktxt("b"), OwnedValue::Text("hello".into()));
+        kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+        kv.insert(ktxt("b"), OwnedValue::Text("hello".into())).unwrap();
         kv.persist_to_file(path).unwrap();
     }
 
@@ -191,7 +191,7 @@ fn load_corrupted_file_returns_error() {
 
     {
         let mut kv = KvStore::new();
-        kv.insert(ktxt("k"), OwnedValue::Integer(123));
+        kv.insert(ktxt("k"), OwnedValue::Integer(123)).unwrap();
         kv.persist_to_file(path).unwrap();
     }
 
@@ -218,3 +218,2094 @@ fn load_corrupted_file_returns_error() {
 
     let _ = std::fs::remove_file(path);
 }
+
+#[test]
+fn checksum_seed_roundtrip_with_same_seed() {
+    let path = "test_store_seed_same.bin";
+
+    {
+        let mut kv = KvStore::with_checksum_seed(0xC0FFEE);
+        kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+        kv.insert(kint(2), OwnedValue::Text("two".into())).unwrap();
+        kv.persist_to_file(path).unwrap();
+    }
+
+    let kv2 = KvStore::load_from_file_with_seed(path, 0xC0FFEE).unwrap();
+
+    assert_eq!(kv2.checksum_seed(), 0xC0FFEE);
+    assert_eq!(
+        kv2.get_owned(&ktxt("a")).unwrap(),
+        Some(OwnedValue::Integer(1))
+    );
+    assert_eq!(
+        kv2.get_owned(&kint(2)).unwrap(),
+        Some(OwnedValue::Text("two".into()))
+    );
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn checksum_seed_mismatch_fails_verification() {
+    let path = "test_store_seed_cross.bin";
+
+    {
+        let mut kv = KvStore::with_checksum_seed(1);
+        kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+        kv.persist_to_file(path).unwrap();
+    }
+
+    // Der Header verrät, dass ein anderer Seed verwendet wurde
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(&bytes[..5], b"K9KV\x03");
+    assert!(matches!(
+        KvStore::load_from_file_with_seed(path, 2),
+        Err(KvError::ChecksumSeedMismatch)
+    ));
+    assert!(matches!(KvStore::load_from_file(path), Err(KvError::ChecksumSeedMismatch)));
+    assert!(KvStore::load_from_file_with_seed(path, 1).is_ok());
+
+    // Ohne Seed geschriebene Dateien lassen sich nicht mit Seed laden
+    let mut plain = KvStore::with_endianness(Endianness::Big);
+    plain.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    plain.persist_to_file(path).unwrap();
+    assert!(matches!(
+        KvStore::load_from_file_with_seed(path, 1),
+        Err(KvError::ChecksumSeedMismatch)
+    ));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn iter_sorted_orders_integers_before_text() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("b"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(kint(10), OwnedValue::Integer(2)).unwrap();
+    kv.insert(ktxt("a"), OwnedValue::Integer(3)).unwrap();
+    kv.insert(kint(-5), OwnedValue::Integer(4)).unwrap();
+    kv.insert(kint(2), OwnedValue::Integer(5)).unwrap();
+
+    let keys: Vec<Key> = kv.iter_sorted().map(|e| e.key.clone()).collect();
+
+    assert_eq!(keys, vec![kint(-5), kint(2), kint(10), ktxt("a"), ktxt("b")]);
+
+    let mut reversed = KvStore::new();
+    for key in keys.iter().rev() {
+        reversed.insert(key.clone(), OwnedValue::Bool(true)).unwrap();
+    }
+    let keys_rev: Vec<Key> = reversed.iter_sorted().map(|e| e.key.clone()).collect();
+    assert_eq!(keys_rev, keys);
+}
+
+#[test]
+fn key_ordering_is_total() {
+    let mut keys = vec![
+        ktxt("b"),
+        kint(i64::MAX),
+        ktxt(""),
+        kint(0),
+        ktxt("B"),
+        kint(i64::MIN),
+        ktxt("ä"),
+        ktxt("a"),
+        kint(-1),
+    ];
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![
+            kint(i64::MIN),
+            kint(-1),
+            kint(0),
+            kint(i64::MAX),
+            ktxt(""),
+            ktxt("B"),
+            ktxt("a"),
+            ktxt("b"),
+            ktxt("ä"),
+        ]
+    );
+
+    // Als BTreeMap-Schlüssel verwendbar
+    let map: std::collections::BTreeMap<Key, u32> =
+        keys.iter().rev().cloned().zip(0..).collect();
+    assert_eq!(map.keys().cloned().collect::<Vec<_>>(), keys);
+    assert!(kint(i64::MAX) < ktxt(""));
+}
+
+#[test]
+fn drain_filter_removes_and_returns_matching_entries() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Text("keep".into())).unwrap();
+    kv.insert(ktxt("c"), OwnedValue::Integer(3)).unwrap();
+    kv.insert(kint(4), OwnedValue::Bool(false)).unwrap();
+
+    let drained = kv.drain_filter(|_, v| matches!(v, BorrowedValue::Integer(_))).unwrap();
+
+    assert_eq!(
+        drained,
+        vec![
+            (ktxt("a"), OwnedValue::Integer(1)),
+            (ktxt("c"), OwnedValue::Integer(3)),
+        ]
+    );
+
+    assert_eq!(kv.get_owned(&ktxt("a")).unwrap(), None);
+    assert_eq!(kv.get_owned(&ktxt("c")).unwrap(), None);
+    assert_eq!(
+        kv.get_owned(&ktxt("b")).unwrap(),
+        Some(OwnedValue::Text("keep".into()))
+    );
+    assert_eq!(
+        kv.get_owned(&kint(4)).unwrap(),
+        Some(OwnedValue::Bool(false))
+    );
+    assert_eq!(kv.keys().count(), 2);
+}
+
+#[test]
+fn update_modifies_value_in_place() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("flag"), OwnedValue::Bool(false)).unwrap();
+    kv.insert(ktxt("counter"), OwnedValue::Integer(41)).unwrap();
+
+    let toggled = kv
+        .update(&ktxt("flag"), |v| {
+            if let OwnedValue::Bool(b) = v {
+                *b = !*b;
+            }
+        })
+        .unwrap();
+    let incremented = kv
+        .update(&ktxt("counter"), |v| {
+            if let OwnedValue::Integer(i) = v {
+                *i += 1;
+            }
+        })
+        .unwrap();
+
+    assert!(toggled);
+    assert!(incremented);
+    assert_eq!(
+        kv.get_owned(&ktxt("flag")).unwrap(),
+        Some(OwnedValue::Bool(true))
+    );
+    assert_eq!(
+        kv.get_owned(&ktxt("counter")).unwrap(),
+        Some(OwnedValue::Integer(42))
+    );
+
+    let missing = kv.update(&ktxt("missing"), |_| panic!("must not be called")).unwrap();
+    assert!(!missing);
+    assert_eq!(kv.get_owned(&ktxt("missing")).unwrap(), None);
+}
+
+#[test]
+fn get_many_returns_positional_results() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(kint(2), OwnedValue::Text("two".into())).unwrap();
+    kv.insert(ktxt("c"), OwnedValue::Blob(vec![3, 3])).unwrap();
+
+    let keys = [ktxt("a"), ktxt("missing"), kint(2), kint(99), ktxt("c")];
+    let values = kv.get_many(&keys).unwrap();
+
+    assert_eq!(values.len(), 5);
+    assert_eq!(values[0], Some(BorrowedValue::Integer(1)));
+    assert_eq!(values[1], None);
+    assert_eq!(values[2], Some(BorrowedValue::Text("two")));
+    assert_eq!(values[3], None);
+    assert_eq!(values[4], Some(BorrowedValue::Blob(&[3, 3])));
+}
+
+#[test]
+fn insert_coerced_picks_value_type() {
+    let mut kv = KvStore::new();
+    kv.insert_coerced(ktxt("int"), "42").unwrap();
+    kv.insert_coerced(ktxt("neg"), "-7").unwrap();
+    kv.insert_coerced(ktxt("bool"), "true").unwrap();
+    kv.insert_coerced(ktxt("float"), "3.14").unwrap();
+    kv.insert_coerced(ktxt("text"), "hello").unwrap();
+    kv.insert_coerced(ktxt("upper"), "TRUE").unwrap();
+
+    assert_eq!(kv.get_owned(&ktxt("int")).unwrap(), Some(OwnedValue::Integer(42)));
+    assert_eq!(kv.get_owned(&ktxt("neg")).unwrap(), Some(OwnedValue::Integer(-7)));
+    assert_eq!(kv.get_owned(&ktxt("bool")).unwrap(), Some(OwnedValue::Bool(true)));
+    assert_eq!(
+        kv.get_owned(&ktxt("float")).unwrap(),
+        Some(OwnedValue::Text("3.14".into()))
+    );
+    assert_eq!(
+        kv.get_owned(&ktxt("text")).unwrap(),
+        Some(OwnedValue::Text("hello".into()))
+    );
+    assert_eq!(
+        kv.get_owned(&ktxt("upper")).unwrap(),
+        Some(OwnedValue::Text("TRUE".into()))
+    );
+}
+
+#[test]
+fn insert_returns_previous_value() {
+    let mut kv = KvStore::new();
+
+    let first = kv.insert(ktxt("k"), OwnedValue::Text("old".into())).unwrap();
+    assert_eq!(first, None);
+
+    let second = kv.insert(ktxt("k"), OwnedValue::Integer(2)).unwrap();
+    assert_eq!(second, Some(OwnedValue::Text("old".into())));
+
+    assert_eq!(
+        kv.get_owned(&ktxt("k")).unwrap(),
+        Some(OwnedValue::Integer(2))
+    );
+}
+
+#[test]
+fn stats_track_dead_bytes_until_compaction() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(0)).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Text("stay".into())).unwrap();
+
+    // Werte wechselnder Größe werden angehängt (gleich große überschreiben in place)
+    for i in 1..=5 {
+        kv.insert(ktxt("a"), OwnedValue::Text("x".repeat(i))).unwrap();
+    }
+
+    let stats = kv.stats();
+    assert_eq!(stats.entry_count, 2);
+    assert_eq!(stats.total_bytes, kv.storage_len());
+    assert!(stats.dead_bytes > 0);
+    assert_eq!(stats.live_bytes + stats.dead_bytes, stats.total_bytes);
+
+    kv.compact().unwrap();
+
+    let compacted: StoreStats = kv.stats();
+    assert_eq!(compacted.dead_bytes, 0);
+    assert_eq!(compacted.live_bytes, stats.live_bytes);
+    assert_eq!(compacted.total_bytes, compacted.live_bytes);
+    assert_eq!(compacted.entry_count, 2);
+}
+
+#[test]
+fn from_pairs_counting_reports_duplicates_and_keeps_last() {
+    let pairs = vec![
+        (ktxt("a"), OwnedValue::Integer(1)),
+        (ktxt("b"), OwnedValue::Integer(2)),
+        (ktxt("a"), OwnedValue::Integer(3)),
+        (kint(1), OwnedValue::Bool(false)),
+        (ktxt("a"), OwnedValue::Integer(4)),
+        (kint(1), OwnedValue::Bool(true)),
+    ];
+
+    let (kv, duplicates) = KvStore::from_pairs_counting(pairs).unwrap();
+
+    assert_eq!(duplicates, 3);
+    assert_eq!(kv.keys().count(), 3);
+    assert_eq!(kv.get_owned(&ktxt("a")).unwrap(), Some(OwnedValue::Integer(4)));
+    assert_eq!(kv.get_owned(&ktxt("b")).unwrap(), Some(OwnedValue::Integer(2)));
+    assert_eq!(kv.get_owned(&kint(1)).unwrap(), Some(OwnedValue::Bool(true)));
+}
+
+#[test]
+fn checked_persist_roundtrip() {
+    let path = "test_store_checked_roundtrip.bin";
+
+    {
+        let mut kv = KvStore::new();
+        kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+        kv.insert(kint(2), OwnedValue::Blob(vec![1, 2, 3])).unwrap();
+        kv.persist_to_file_checked(path).unwrap();
+    }
+
+    let kv2 = KvStore::load_from_file_checked(path).unwrap();
+    assert_eq!(kv2.get_owned(&ktxt("a")).unwrap(), Some(OwnedValue::Integer(1)));
+    assert_eq!(
+        kv2.get_owned(&kint(2)).unwrap(),
+        Some(OwnedValue::Blob(vec![1, 2, 3]))
+    );
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn checked_persist_roundtrip_with_seed_and_checksum() {
+    let path = "test_store_checked_codec.bin";
+
+    let mut seeded = KvStore::with_checksum_seed(7);
+    seeded.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    seeded.persist_to_file_checked(path).unwrap();
+
+    let kv = KvStore::load_from_file_checked_with_seed(path, 7).unwrap();
+    assert_eq!(kv.get_owned(&ktxt("a")).unwrap(), Some(OwnedValue::Integer(1)));
+    assert!(KvStore::load_from_file_checked(path).is_err());
+
+    let mut castagnoli = KvStore::with_checksum(Checksum::Crc32Castagnoli);
+    castagnoli.insert(kint(2), OwnedValue::Text("zwei".into())).unwrap();
+    castagnoli.persist_to_file_checked(path).unwrap();
+
+    let kv = KvStore::load_from_file_checked_with_checksum(path, Checksum::Crc32Castagnoli).unwrap();
+    assert_eq!(kv.get_owned(&kint(2)).unwrap(), Some(OwnedValue::Text("zwei".into())));
+    assert_eq!(kv.checksum(), Checksum::Crc32Castagnoli);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn checked_load_detects_tail_damage_entry_crcs_miss() {
+    let path = "test_store_checked_tail.bin";
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Integer(2)).unwrap();
+    kv.persist_to_file_checked(path).unwrap();
+    let full = std::fs::read(path).unwrap();
+
+    // Letzten Record entfernen, Datei-CRC behalten: jeder übrige Eintrag ist für sich gültig
+    let mut last_only = KvStore::new();
+    last_only.insert(ktxt("b"), OwnedValue::Integer(2)).unwrap();
+    last_only.persist_to_file(path).unwrap();
+    let last_record_len = std::fs::read(path).unwrap().len();
+
+    let crc_start = full.len() - 4;
+    let mut truncated = full[..crc_start - last_record_len].to_vec();
+    truncated.extend_from_slice(&full[crc_start..]);
+    std::fs::write(path, &truncated).unwrap();
+
+    let res = KvStore::load_from_file_checked(path);
+    assert!(matches!(res, Err(KvError::FileChecksumMismatch { .. })));
+
+    // Ein gekipptes Byte in der Datei-CRC selbst wird ebenso erkannt
+    let mut flipped = full.clone();
+    let last = flipped.len() - 1;
+    flipped[last] ^= 0xFF;
+    std::fs::write(path, &flipped).unwrap();
+
+    let res = KvStore::load_from_file_checked(path);
+    assert!(matches!(res, Err(KvError::FileChecksumMismatch { .. })));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn persist_to_directory_is_rejected() {
+    let dir = "test_store_persist_dir";
+    let _ = std::fs::create_dir(dir);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+
+    let res = kv.persist_to_file(dir);
+    assert!(matches!(res, Err(KvError::IsDirectory(_))));
+
+    let _ = std::fs::remove_dir(dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn persist_through_symlink_respects_option() {
+    let target = "test_store_symlink_target.bin";
+    let link = "test_store_symlink_link.bin";
+    let _ = std::fs::remove_file(target);
+    let _ = std::fs::remove_file(link);
+    std::os::unix::fs::symlink(target, link).unwrap();
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+
+    let strict = PersistOptions { no_follow_symlinks: true };
+    let res = kv.persist_to_file_with(link, &strict);
+    assert!(matches!(res, Err(KvError::SymlinkRefused(_))));
+    assert!(!std::path::Path::new(target).exists());
+
+    kv.persist_to_file_with(link, &PersistOptions::default()).unwrap();
+    let loaded = KvStore::load_from_file(target).unwrap();
+    assert_eq!(loaded.get_owned(&ktxt("a")).unwrap(), Some(OwnedValue::Integer(1)));
+
+    let _ = std::fs::remove_file(link);
+    let _ = std::fs::remove_file(target);
+}
+
+#[test]
+fn crc32c_roundtrip() {
+    let path = "test_store_crc32c.bin";
+
+    {
+        let mut kv = KvStore::with_checksum(Checksum::Crc32Castagnoli);
+        kv.insert(ktxt("a"), OwnedValue::Text("castagnoli".into())).unwrap();
+        kv.insert(kint(1), OwnedValue::Blob(vec![0xAB; 32])).unwrap();
+        kv.persist_to_file(path).unwrap();
+    }
+
+    let kv2 = KvStore::load_from_file_with_checksum(path, Checksum::Crc32Castagnoli).unwrap();
+
+    assert_eq!(kv2.checksum(), Checksum::Crc32Castagnoli);
+    assert_eq!(
+        kv2.get_owned(&ktxt("a")).unwrap(),
+        Some(OwnedValue::Text("castagnoli".into()))
+    );
+    assert_eq!(
+        kv2.get_owned(&kint(1)).unwrap(),
+        Some(OwnedValue::Blob(vec![0xAB; 32]))
+    );
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn checksum_algorithm_mismatch_is_detected() {
+    let crc32c_path = "test_store_crc_mismatch_c.bin";
+    let default_path = "test_store_crc_mismatch_default.bin";
+
+    let mut kv = KvStore::with_checksum(Checksum::Crc32Castagnoli);
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.persist_to_file(crc32c_path).unwrap();
+
+    let res = KvStore::load_from_file(crc32c_path);
+    assert!(matches!(
+        res,
+        Err(KvError::ChecksumAlgorithmMismatch {
+            expected: Checksum::Crc32IsoHdlc,
+            found: Checksum::Crc32Castagnoli,
+        })
+    ));
+
+    // Dateien im Standardformat haben keinen Header, hier schlägt die Eintrags-CRC fehl
+    let mut plain = KvStore::new();
+    plain.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    plain.persist_to_file(default_path).unwrap();
+
+    let res = KvStore::load_from_file_with_checksum(default_path, Checksum::Crc32Castagnoli);
+    assert!(matches!(res, Err(KvError::Corrupted(_))));
+
+    let _ = std::fs::remove_file(crc32c_path);
+    let _ = std::fs::remove_file(default_path);
+}
+
+#[test]
+fn compressed_blob_roundtrip_shrinks_storage() {
+    let path = "test_store_compressed.bin";
+    let big: Vec<u8> = b"k9-notes ".iter().copied().cycle().take(1024 * 1024).collect();
+
+    let mut kv = KvStore::with_compression(4096);
+    kv.insert(ktxt("big"), OwnedValue::Blob(big.clone())).unwrap();
+    kv.insert(ktxt("small"), OwnedValue::Blob(vec![1, 2, 3])).unwrap();
+
+    assert!(kv.storage_len() < big.len() / 10);
+    assert_eq!(kv.get_owned(&ktxt("big")).unwrap(), Some(OwnedValue::Blob(big.clone())));
+
+    // Kleine Blobs bleiben unkomprimiert und damit ausleihbar
+    assert_eq!(
+        kv.get_borrowed(&ktxt("small")).unwrap(),
+        Some(BorrowedValue::Blob(&[1, 2, 3]))
+    );
+    assert!(matches!(
+        kv.get_borrowed(&ktxt("big")),
+        Err(KvError::CompressedValue)
+    ));
+
+    kv.persist_to_file(path).unwrap();
+    assert!((std::fs::metadata(path).unwrap().len() as usize) < big.len() / 10);
+
+    let loaded = KvStore::load_from_file(path).unwrap();
+    assert_eq!(loaded.get_owned(&ktxt("big")).unwrap(), Some(OwnedValue::Blob(big)));
+    assert_eq!(loaded.keys().count(), 2);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn compressed_blob_survives_compaction() {
+    let big = vec![0u8; 100_000];
+
+    let mut kv = KvStore::with_compression(1024);
+    kv.insert(ktxt("big"), OwnedValue::Blob(big.clone())).unwrap();
+    kv.insert(ktxt("x"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("x"), OwnedValue::Integer(2)).unwrap();
+
+    kv.compact().unwrap();
+
+    assert_eq!(kv.get_owned(&ktxt("big")).unwrap(), Some(OwnedValue::Blob(big)));
+    assert_eq!(kv.get_owned(&ktxt("x")).unwrap(), Some(OwnedValue::Integer(2)));
+}
+
+#[test]
+fn write_entries_to_file_streams_loadable_file() {
+    let path = "test_stream_ingest.bin";
+    let _ = std::fs::remove_file(path);
+
+    const N: i64 = 100_000;
+    let items = (0..N).map(|i| {
+        let value = if i % 2 == 0 {
+            OwnedValue::Integer(i * 3)
+        } else {
+            OwnedValue::Text(format!("wert-{i}"))
+        };
+        (kint(i), value)
+    });
+
+    KvStore::write_entries_to_file(path, items).unwrap();
+
+    let kv = KvStore::load_from_file(path).unwrap();
+    assert_eq!(kv.keys().count(), N as usize);
+
+    // Stichproben
+    for i in [0, 1, 4_242, 77_777, N - 1] {
+        let expected = if i % 2 == 0 {
+            OwnedValue::Integer(i * 3)
+        } else {
+            OwnedValue::Text(format!("wert-{i}"))
+        };
+        assert_eq!(kv.get_owned(&kint(i)).unwrap(), Some(expected));
+    }
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn export_csv_escapes_fields() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a,b"), OwnedValue::Text("sagt \"hallo\"".into())).unwrap();
+    kv.insert(ktxt("zeile\numbruch"), OwnedValue::Bool(true)).unwrap();
+    kv.insert(kint(-5), OwnedValue::Integer(42)).unwrap();
+    kv.insert(ktxt("blob"), OwnedValue::Blob(vec![0xff, 0x00, 0x10])).unwrap();
+
+    let mut out = Vec::new();
+    kv.export_csv(&mut out).unwrap();
+
+    let mut reader = csv::Reader::from_reader(out.as_slice());
+    assert_eq!(
+        reader.headers().unwrap(),
+        vec!["key_type", "key", "value_type", "value"]
+    );
+
+    let rows: Vec<Vec<String>> = reader
+        .records()
+        .map(|r| r.unwrap().iter().map(str::to_string).collect())
+        .collect();
+
+    // Sortiert: Integer-Schlüssel vor Text-Schlüsseln
+    assert_eq!(
+        rows,
+        vec![
+            vec!["integer", "-5", "integer", "42"],
+            vec!["text", "a,b", "text", "sagt \"hallo\""],
+            vec!["text", "blob", "blob", "/wAQ"],
+            vec!["text", "zeile\numbruch", "bool", "true"],
+        ]
+    );
+}
+
+fn merge_fixture() -> (KvStore, KvStore) {
+    let mut base = KvStore::new();
+    base.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    base.insert(ktxt("b"), OwnedValue::Integer(10)).unwrap();
+
+    let mut delta = KvStore::new();
+    delta.insert(ktxt("b"), OwnedValue::Integer(5)).unwrap();
+    delta.insert(ktxt("c"), OwnedValue::Text("neu".into())).unwrap();
+
+    (base, delta)
+}
+
+#[test]
+fn merge_keep_existing() {
+    let (mut base, delta) = merge_fixture();
+    base.merge(delta, MergePolicy::KeepExisting).unwrap();
+
+    assert_eq!(base.get_owned(&ktxt("a")).unwrap(), Some(OwnedValue::Integer(1)));
+    assert_eq!(base.get_owned(&ktxt("b")).unwrap(), Some(OwnedValue::Integer(10)));
+    assert_eq!(base.get_owned(&ktxt("c")).unwrap(), Some(OwnedValue::Text("neu".into())));
+}
+
+#[test]
+fn merge_overwrite() {
+    let (mut base, delta) = merge_fixture();
+    base.merge(delta, MergePolicy::Overwrite).unwrap();
+
+    assert_eq!(base.get_owned(&ktxt("a")).unwrap(), Some(OwnedValue::Integer(1)));
+    assert_eq!(base.get_owned(&ktxt("b")).unwrap(), Some(OwnedValue::Integer(5)));
+    assert_eq!(base.get_owned(&ktxt("c")).unwrap(), Some(OwnedValue::Text("neu".into())));
+}
+
+#[test]
+fn merge_custom_sums_integers() {
+    let (mut base, delta) = merge_fixture();
+    let sum = MergePolicy::Custom(Box::new(|_key, existing, incoming| {
+        match (existing, incoming) {
+            (BorrowedValue::Integer(a), BorrowedValue::Integer(b)) => OwnedValue::Integer(a + b),
+            (_, incoming) => incoming.to_owned(),
+        }
+    }));
+    base.merge(delta, sum).unwrap();
+
+    assert_eq!(base.get_owned(&ktxt("a")).unwrap(), Some(OwnedValue::Integer(1)));
+    assert_eq!(base.get_owned(&ktxt("b")).unwrap(), Some(OwnedValue::Integer(15)));
+    assert_eq!(base.get_owned(&ktxt("c")).unwrap(), Some(OwnedValue::Text("neu".into())));
+}
+
+#[test]
+fn type_of_reports_value_types() {
+    let mut kv = KvStore::with_compression(16);
+    kv.insert(ktxt("i"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Bool(true)).unwrap();
+    kv.insert(ktxt("t"), OwnedValue::Text("x".into())).unwrap();
+    kv.insert(ktxt("blob"), OwnedValue::Blob(vec![1, 2])).unwrap();
+    // Komprimierter Blob meldet ebenfalls Blob
+    kv.insert(ktxt("zblob"), OwnedValue::Blob(vec![0; 1024])).unwrap();
+
+    assert_eq!(kv.type_of(&ktxt("i")).unwrap(), Some(ValueType::Integer));
+    assert_eq!(kv.type_of(&ktxt("b")).unwrap(), Some(ValueType::Bool));
+    assert_eq!(kv.type_of(&ktxt("t")).unwrap(), Some(ValueType::Text));
+    assert_eq!(kv.type_of(&ktxt("blob")).unwrap(), Some(ValueType::Blob));
+    assert_eq!(kv.type_of(&ktxt("zblob")).unwrap(), Some(ValueType::Blob));
+    assert_eq!(kv.type_of(&ktxt("fehlt")).unwrap(), None);
+}
+
+#[test]
+fn insert_rejects_value_over_limit() {
+    // Header (13) + Längenfeld (8) + 100 Bytes = 121
+    let mut kv = KvStore::with_limits(Limits { max_value_bytes: Some(120) });
+
+    let res = kv.insert(ktxt("gross"), OwnedValue::Blob(vec![0; 100]));
+    assert!(matches!(res, Err(KvError::ValueTooLarge { size: 121, limit: 120 })));
+    assert_eq!(kv.get_owned(&ktxt("gross")).unwrap(), None);
+    assert_eq!(kv.storage_len(), 0);
+
+    kv.insert(ktxt("passt"), OwnedValue::Blob(vec![0; 99])).unwrap();
+    assert_eq!(kv.get_owned(&ktxt("passt")).unwrap(), Some(OwnedValue::Blob(vec![0; 99])));
+}
+
+#[test]
+fn load_rejects_value_over_limit() {
+    let path = "test_limits_load.bin";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("klein"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("gross"), OwnedValue::Blob(vec![7; 1000])).unwrap();
+    kv.persist_to_file(path).unwrap();
+
+    let res = KvStore::load_from_file_with_limits(path, Limits { max_value_bytes: Some(512) });
+    assert!(matches!(res, Err(KvError::ValueTooLarge { limit: 512, .. })));
+
+    let loaded = KvStore::load_from_file_with_limits(path, Limits { max_value_bytes: Some(2048) }).unwrap();
+    assert_eq!(loaded.keys().count(), 2);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn load_reports_truncated_final_key_as_corruption() {
+    let path = "test_truncated_key.bin";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.persist_to_file(path).unwrap();
+    let complete = std::fs::read(path).unwrap();
+
+    kv.insert(ktxt("ein_langer_schluessel"), OwnedValue::Integer(2)).unwrap();
+    kv.persist_to_file(path).unwrap();
+    let bytes = std::fs::read(path).unwrap();
+
+    // Datei endet mitten im Schlüssel des zweiten Paares
+    std::fs::write(path, &bytes[..complete.len() + 20]).unwrap();
+
+    // Der Offset zeigt auf den Anfang des unvollständigen Paares
+    let res = KvStore::load_from_file(path);
+    assert!(matches!(res, Err(KvError::UnexpectedEof { offset }) if offset == complete.len()));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn load_rejects_trailing_junk() {
+    let path = "test_trailing_junk.bin";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(kint(2), OwnedValue::Text("zwei".into())).unwrap();
+    kv.persist_to_file(path).unwrap();
+    let complete = std::fs::read(path).unwrap();
+
+    // Kürzer als ein Header, genau ein Header, länger als ein Header
+    for junk_len in [1, 7, 12, 13, 40] {
+        let mut bytes = complete.clone();
+        bytes.extend(std::iter::repeat_n(0xAB, junk_len));
+        std::fs::write(path, &bytes).unwrap();
+
+        // Ab einem vollen Header ist die Länge 0xABAB... unmöglich groß
+        let res = KvStore::load_from_file(path);
+        let ok = if junk_len < 13 {
+            matches!(res, Err(KvError::UnexpectedEof { offset }) if offset == complete.len())
+        } else {
+            matches!(res, Err(KvError::Corrupted(DecodeError::LengthTooLarge(_))))
+        };
+        assert!(ok, "junk_len={junk_len}: {:?}", res.err());
+    }
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn sync_store_concurrent_readers_and_writer() {
+    use std::sync::Arc;
+    use std::thread;
+
+    const ROUNDS: i64 = 2_000;
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("zaehler"), OwnedValue::Integer(0)).unwrap();
+    kv.insert(ktxt("fest"), OwnedValue::Text("bleibt".into())).unwrap();
+    let store = Arc::new(SyncKvStore::new(kv));
+
+    let writer = {
+        let store = Arc::clone(&store);
+        thread::spawn(move || {
+            for i in 1..=ROUNDS {
+                store.insert(ktxt("zaehler"), OwnedValue::Integer(i)).unwrap();
+                store.insert(kint(i), OwnedValue::Integer(i)).unwrap();
+                if i % 2 == 0 {
+                    store.delete(&kint(i - 1));
+                }
+            }
+        })
+    };
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let store = Arc::clone(&store);
+            thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..ROUNDS {
+                    // Der Zähler darf nie zurücklaufen und nie kaputt gelesen werden
+                    match store.get_owned(&ktxt("zaehler")).unwrap() {
+                        Some(OwnedValue::Integer(n)) => {
+                            assert!(n >= last);
+                            last = n;
+                        }
+                        other => panic!("unexpected value {other:?}"),
+                    }
+                    assert_eq!(
+                        store.get_owned(&ktxt("fest")).unwrap(),
+                        Some(OwnedValue::Text("bleibt".into()))
+                    );
+                    let snapshot = store.snapshot();
+                    assert!(snapshot.get_owned(&ktxt("zaehler")).unwrap().is_some());
+                }
+            })
+        })
+        .collect();
+
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    let kv = Arc::try_unwrap(store).ok().unwrap().into_inner();
+    assert_eq!(kv.get_owned(&ktxt("zaehler")).unwrap(), Some(OwnedValue::Integer(ROUNDS)));
+    assert_eq!(kv.get_owned(&kint(ROUNDS)).unwrap(), Some(OwnedValue::Integer(ROUNDS)));
+    assert_eq!(kv.get_owned(&kint(ROUNDS - 1)).unwrap(), None);
+}
+
+#[test]
+fn read_blob_into_reuses_buffer() {
+    let mut kv = KvStore::with_compression(256);
+    kv.insert(ktxt("a"), OwnedValue::Blob(vec![1; 10])).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Blob(vec![2; 100])).unwrap();
+    // Wird komprimiert gespeichert
+    kv.insert(ktxt("c"), OwnedValue::Blob(vec![3; 4096])).unwrap();
+    kv.insert(ktxt("zahl"), OwnedValue::Integer(5)).unwrap();
+
+    let mut buf = Vec::with_capacity(8192);
+    let ptr = buf.as_ptr();
+
+    for (key, byte, len) in [("a", 1u8, 10), ("b", 2, 100), ("c", 3, 4096), ("a", 1, 10)] {
+        buf.clear();
+        assert_eq!(kv.read_blob_into(&ktxt(key), &mut buf).unwrap(), Some(len));
+        assert_eq!(buf, vec![byte; len]);
+    }
+    // Kein Umkopieren in einen neuen Puffer
+    assert_eq!(buf.as_ptr(), ptr);
+
+    // Anhängen statt Überschreiben
+    kv.read_blob_into(&ktxt("b"), &mut buf).unwrap();
+    assert_eq!(buf.len(), 110);
+
+    assert_eq!(kv.read_blob_into(&ktxt("fehlt"), &mut buf).unwrap(), None);
+    assert!(matches!(
+        kv.read_blob_into(&ktxt("zahl"), &mut buf),
+        Err(KvError::TypeMismatch { expected: ValueType::Blob, found: ValueType::Integer })
+    ));
+    assert_eq!(buf.len(), 110);
+}
+
+#[test]
+fn iter_bytes_walks_persisted_buffer() {
+    let path = "test_iter_bytes.bin";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(kint(2), OwnedValue::Text("zwei".into())).unwrap();
+    kv.persist_to_file(path).unwrap();
+    let mut bytes = std::fs::read(path).unwrap();
+    let _ = std::fs::remove_file(path);
+
+    let items: Vec<_> = KvStore::iter_bytes(&bytes).map(|r| r.unwrap()).collect();
+    assert_eq!(
+        items,
+        vec![
+            BorrowedValue::Text("a"),
+            BorrowedValue::Integer(1),
+            BorrowedValue::Integer(2),
+            BorrowedValue::Text("zwei"),
+        ]
+    );
+
+    // Erstes Payload-Byte des ersten Werts: Schlüssel "a" (13 + 8 + 1) + Header (13)
+    bytes[22 + 13] ^= 0xFF;
+    let items: Vec<_> = KvStore::iter_bytes(&bytes).collect();
+    assert_eq!(items.len(), 4);
+    assert!(matches!(items[0], Ok(BorrowedValue::Text("a"))));
+    assert!(matches!(
+        items[1],
+        Err(KvError::Corrupted(DecodeError::ChecksumMismatch { .. }))
+    ));
+    assert!(matches!(items[3], Ok(BorrowedValue::Text("zwei"))));
+
+    // Abgeschnittener Puffer endet mit UnexpectedEof
+    let items: Vec<_> = KvStore::iter_bytes(&bytes[..bytes.len() - 2]).collect();
+    assert_eq!(items.len(), 4);
+    // Der letzte Eintrag ist der Text "zwei" (13 + 8 + 4)
+    let last = bytes.len() - 25;
+    assert!(matches!(items[3], Err(KvError::UnexpectedEof { offset }) if offset == last));
+}
+
+#[test]
+fn display_key_and_value() {
+    assert_eq!(kint(-42).to_string(), "-42");
+    assert_eq!(ktxt("user:1").to_string(), "user:1");
+    assert_eq!(ktxt("").to_string(), "");
+
+    assert_eq!(OwnedValue::Integer(7).to_string(), "7");
+    assert_eq!(OwnedValue::Bool(false).to_string(), "false");
+    assert_eq!(OwnedValue::Text("hallo welt".into()).to_string(), "hallo welt");
+    assert_eq!(OwnedValue::Text(String::new()).to_string(), "");
+    assert_eq!(OwnedValue::Blob(vec![1, 2, 3]).to_string(), "<3 bytes>");
+    assert_eq!(OwnedValue::Blob(Vec::new()).to_string(), "<0 bytes>");
+
+    assert_eq!(
+        format!("{} = {}", ktxt("k"), OwnedValue::Integer(1)),
+        "k = 1"
+    );
+}
+
+#[test]
+fn big_endian_store_roundtrip() {
+    let path = "test_big_endian.kv";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::with_endianness(Endianness::Big);
+    kv.insert(ktxt("a"), OwnedValue::Integer(-2)).unwrap();
+    kv.insert(kint(7), OwnedValue::Text("sieben".into())).unwrap();
+    kv.insert(ktxt("blob"), OwnedValue::Blob(vec![1, 2, 3])).unwrap();
+    kv.insert(ktxt("flag"), OwnedValue::Bool(true)).unwrap();
+    kv.persist_to_file(path).unwrap();
+
+    let bytes = std::fs::read(path).unwrap();
+    // Dateiheader: Magic, Version 2, Prüfsummenalgorithmus, Byte-Reihenfolge
+    assert_eq!(&bytes[..7], b"K9KV\x02\x00\x01");
+    // Längenfeld des ersten Schlüssels ("a": 4 + 1 + 8 + 1 Bytes) in Big-Endian
+    assert_eq!(&bytes[7..15], &14u64.to_be_bytes());
+
+    // Ein Standard-Leser übernimmt die Byte-Reihenfolge aus dem Dateiheader
+    let loaded = KvStore::load_from_file(path).unwrap();
+    assert_eq!(loaded.endianness(), Endianness::Big);
+    assert_eq!(loaded.get_borrowed(&ktxt("a")).unwrap(), Some(BorrowedValue::Integer(-2)));
+    assert_eq!(loaded.get_borrowed(&kint(7)).unwrap(), Some(BorrowedValue::Text("sieben")));
+    assert_eq!(loaded.get_borrowed(&ktxt("blob")).unwrap(), Some(BorrowedValue::Blob(&[1, 2, 3])));
+    assert_eq!(loaded.get_borrowed(&ktxt("flag")).unwrap(), Some(BorrowedValue::Bool(true)));
+
+    let walked: Vec<_> = KvStore::iter_bytes(&bytes).map(|r| r.unwrap()).collect();
+    assert_eq!(walked.len(), 8);
+    assert_eq!(walked[1], BorrowedValue::Integer(-2));
+
+    // Falsche Byte-Reihenfolge im Header: Einträge werden als Little-Endian gelesen
+    let mut flipped = bytes.clone();
+    flipped[6] = 0;
+    std::fs::write(path, &flipped).unwrap();
+    assert!(KvStore::load_from_file(path).is_err());
+
+    flipped[6] = 7;
+    std::fs::write(path, &flipped).unwrap();
+    assert!(matches!(
+        KvStore::load_from_file(path),
+        Err(KvError::Corrupted(DecodeError::UnknownEndianness(7)))
+    ));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn little_endian_files_stay_headerless() {
+    let path = "test_little_endian.kv";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::with_endianness(Endianness::Little);
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.persist_to_file(path).unwrap();
+
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(&bytes[..8], &14u64.to_le_bytes());
+
+    let loaded = KvStore::load_from_file(path).unwrap();
+    assert_eq!(loaded.endianness(), Endianness::Little);
+    assert_eq!(loaded.get_borrowed(&ktxt("a")).unwrap(), Some(BorrowedValue::Integer(1)));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn appending_store_replays_writes_and_tombstones() {
+    let path = "test_appending_replay.kv";
+    let _ = std::fs::remove_file(path);
+
+    {
+        let mut log = KvStore::open_appending(path).unwrap();
+        assert!(log.is_empty());
+        log.set_sync(true);
+
+        assert_eq!(log.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap(), None);
+        log.insert(ktxt("b"), OwnedValue::Text("zwei".into())).unwrap();
+        assert_eq!(
+            log.insert(ktxt("a"), OwnedValue::Integer(3)).unwrap(),
+            Some(OwnedValue::Integer(1))
+        );
+        assert!(log.delete(&ktxt("b")).unwrap());
+        assert!(!log.delete(&ktxt("fehlt")).unwrap());
+        log.insert(kint(4), OwnedValue::Bool(true)).unwrap();
+    }
+
+    let mut log = KvStore::open_appending(path).unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log.get_borrowed(&ktxt("a")).unwrap(), Some(BorrowedValue::Integer(3)));
+    assert!(!log.contains_key(&ktxt("b")));
+    assert_eq!(log.get_owned(&kint(4)).unwrap(), Some(OwnedValue::Bool(true)));
+
+    // Gelöschter Schlüssel kann wieder angelegt werden
+    log.insert(ktxt("b"), OwnedValue::Integer(5)).unwrap();
+    drop(log);
+
+    let log = KvStore::open_appending(path).unwrap();
+    assert_eq!(log.get_borrowed(&ktxt("b")).unwrap(), Some(BorrowedValue::Integer(5)));
+    assert_eq!(log.store().keys().count(), 3);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn appending_store_sequence_resumes_after_reopen() {
+    let path = "test_appending_seq.kv";
+    let _ = std::fs::remove_file(path);
+
+    let mut seen = Vec::new();
+    {
+        let mut log = KvStore::open_appending(path).unwrap();
+        assert_eq!(log.current_seq(), 0);
+        log.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+        seen.push(log.current_seq());
+        log.insert(ktxt("a"), OwnedValue::Integer(2)).unwrap();
+        seen.push(log.current_seq());
+
+        // Nichts geschrieben, keine neue Nummer
+        assert!(!log.delete(&ktxt("fehlt")).unwrap());
+        assert_eq!(log.current_seq(), 2);
+    }
+
+    // Nach dem Öffnen geht es ohne Wiederholung weiter
+    let mut log = KvStore::open_appending(path).unwrap();
+    assert_eq!(log.current_seq(), 2);
+    log.delete(&ktxt("a")).unwrap();
+    seen.push(log.current_seq());
+    log.insert(ktxt("b"), OwnedValue::Integer(3)).unwrap();
+    seen.push(log.current_seq());
+
+    assert_eq!(seen, vec![1, 2, 3, 4]);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn appending_store_recovers_from_torn_write() {
+    let path = "test_appending_crash.kv";
+    let _ = std::fs::remove_file(path);
+
+    {
+        let mut log = KvStore::open_appending(path).unwrap();
+        log.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+        log.insert(ktxt("b"), OwnedValue::Text("unvollständig".into())).unwrap();
+    }
+
+    // Absturz mitten im letzten Datensatz simulieren
+    let full = std::fs::read(path).unwrap();
+    std::fs::write(path, &full[..full.len() - 5]).unwrap();
+    let intact_len = (22 + 21) as u64; // Schlüssel "a" + Integer-Wert
+
+    // Ohne ausdrückliche Reparatur wird nichts abgeschnitten
+    assert!(matches!(
+        KvStore::open_appending(path),
+        Err(KvError::UnexpectedEof { offset: 43 })
+    ));
+    assert_eq!(std::fs::metadata(path).unwrap().len(), full.len() as u64 - 5);
+
+    {
+        let (mut log, dropped) = KvStore::open_appending_truncating(path).unwrap();
+        assert_eq!(dropped, full.len() as u64 - 5 - intact_len);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log.get_borrowed(&ktxt("a")).unwrap(), Some(BorrowedValue::Integer(1)));
+        assert!(!log.contains_key(&ktxt("b")));
+        assert_eq!(std::fs::metadata(path).unwrap().len(), intact_len);
+
+        log.insert(ktxt("c"), OwnedValue::Integer(3)).unwrap();
+    }
+
+    // Der abgeschnittene Rest stört spätere Datensätze nicht
+    let log = KvStore::open_appending(path).unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log.get_borrowed(&ktxt("c")).unwrap(), Some(BorrowedValue::Integer(3)));
+
+    // Beschädigung mitten im Log ist dagegen ein Fehler
+    drop(log);
+    let mut bytes = std::fs::read(path).unwrap();
+    bytes[22 + 13] ^= 0xFF;
+    std::fs::write(path, &bytes).unwrap();
+    assert!(matches!(
+        KvStore::open_appending(path),
+        Err(KvError::Corrupted(DecodeError::ChecksumMismatch { .. }))
+    ));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn appending_store_keeps_records_after_corrupted_length() {
+    let path = "test_appending_bad_length.kv";
+    let _ = std::fs::remove_file(path);
+
+    {
+        let mut log = KvStore::open_appending(path).unwrap();
+        log.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+        log.insert(ktxt("b"), OwnedValue::Integer(2)).unwrap();
+        log.insert(ktxt("c"), OwnedValue::Integer(3)).unwrap();
+    }
+
+    // Längenfeld des zweiten Schlüssels zeigt über das Dateiende hinaus
+    let mut bytes = std::fs::read(path).unwrap();
+    bytes[43..51].copy_from_slice(&1_000_000u64.to_le_bytes());
+    std::fs::write(path, &bytes).unwrap();
+
+    assert!(matches!(
+        KvStore::open_appending(path),
+        Err(KvError::UnexpectedEof { offset: 43 })
+    ));
+    // Die Datei bleibt unverändert, die Datensätze dahinter gehen nicht verloren
+    assert_eq!(std::fs::read(path).unwrap(), bytes);
+
+    // Die ausdrückliche Reparatur meldet, wie viel verworfen wird
+    let (log, dropped) = KvStore::open_appending_truncating(path).unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(dropped, 2 * 43);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn load_honors_tombstones() {
+    let path = "test_load_tombstones.kv";
+    let _ = std::fs::remove_file(path);
+
+    {
+        let mut log = KvStore::open_appending(path).unwrap();
+        log.insert(ktxt("weg"), OwnedValue::Integer(1)).unwrap();
+        log.insert(ktxt("bleibt"), OwnedValue::Integer(2)).unwrap();
+        log.insert(ktxt("neu"), OwnedValue::Integer(3)).unwrap();
+        log.delete(&ktxt("weg")).unwrap();
+        log.delete(&ktxt("neu")).unwrap();
+        log.insert(ktxt("neu"), OwnedValue::Integer(4)).unwrap();
+    }
+
+    let kv = KvStore::load_from_file(path).unwrap();
+    assert_eq!(kv.get_borrowed(&ktxt("weg")).unwrap(), None);
+    assert_eq!(kv.get_borrowed(&ktxt("bleibt")).unwrap(), Some(BorrowedValue::Integer(2)));
+    assert_eq!(kv.get_borrowed(&ktxt("neu")).unwrap(), Some(BorrowedValue::Integer(4)));
+    assert_eq!(kv.keys().count(), 2);
+
+    // Neu geschriebene Dateien enthalten keine Tombstones mehr
+    kv.persist_to_file(path).unwrap();
+    let len = std::fs::metadata(path).unwrap().len();
+    // Schlüssel "bleibt" und "neu" (je 21 Bytes + Text) plus zwei Integer-Werte
+    assert_eq!(len, (21 + 6) + (21 + 3) + 2 * 21);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn owned_iterators_outlive_the_borrow() {
+    let mut kv = KvStore::with_compression(8);
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(kint(2), OwnedValue::Text("zwei".into())).unwrap();
+    kv.insert(ktxt("z"), OwnedValue::Blob(vec![0; 64])).unwrap();
+
+    let values: Vec<OwnedValue> = kv.values_owned().collect();
+    let entries: Vec<(Key, OwnedValue)> = kv.entries_owned().collect();
+
+    // Die Store-Ausleihe ist beendet, &mut ist wieder möglich
+    kv.insert(ktxt("a"), OwnedValue::Integer(9)).unwrap();
+    kv.delete(&kint(2));
+
+    assert_eq!(
+        values,
+        vec![
+            OwnedValue::Integer(1),
+            OwnedValue::Text("zwei".into()),
+            OwnedValue::Blob(vec![0; 64]),
+        ]
+    );
+    assert_eq!(entries[0], (ktxt("a"), OwnedValue::Integer(1)));
+    assert_eq!(entries[1], (kint(2), OwnedValue::Text("zwei".into())));
+    assert_eq!(entries.len(), 3);
+    assert_eq!(kv.entries_owned().count(), 2);
+}
+
+#[test]
+fn drain_empties_the_store() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Bool(false)).unwrap();
+    kv.insert(kint(3), OwnedValue::Blob(vec![3])).unwrap();
+
+    let drained: Vec<(Key, OwnedValue)> = kv.drain().collect();
+    assert_eq!(
+        drained,
+        vec![
+            (ktxt("a"), OwnedValue::Integer(1)),
+            (ktxt("b"), OwnedValue::Bool(false)),
+            (kint(3), OwnedValue::Blob(vec![3])),
+        ]
+    );
+    assert_eq!(kv.keys().count(), 0);
+    assert_eq!(kv.storage_len(), 0);
+
+    // Der Store bleibt danach normal benutzbar
+    kv.insert(ktxt("c"), OwnedValue::Integer(4)).unwrap();
+    assert_eq!(kv.get_borrowed(&ktxt("c")).unwrap(), Some(BorrowedValue::Integer(4)));
+}
+
+#[test]
+fn partial_drain_still_clears() {
+    let mut kv = KvStore::new();
+    for i in 0..5 {
+        kv.insert(kint(i), OwnedValue::Integer(i * 10)).unwrap();
+    }
+
+    let first: Vec<_> = kv.drain().take(2).collect();
+    assert_eq!(first, vec![(kint(0), OwnedValue::Integer(0)), (kint(1), OwnedValue::Integer(10))]);
+    assert_eq!(kv.keys().count(), 0);
+    assert_eq!(kv.get_borrowed(&kint(4)).unwrap(), None);
+
+    // Iterator ohne Verbrauch verwerfen
+    kv.insert(ktxt("x"), OwnedValue::Integer(1)).unwrap();
+    drop(kv.drain());
+    assert_eq!(kv.keys().count(), 0);
+}
+
+#[test]
+fn entry_size_reports_serialized_length() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("n"), OwnedValue::Integer(7)).unwrap();
+    kv.insert(ktxt("blob"), OwnedValue::Blob(vec![0xAB; 4096])).unwrap();
+
+    // Header (13) + 8 Bytes Integer bzw. 8 Bytes Länge + Blob-Daten
+    assert_eq!(kv.entry_size(&ktxt("n")), Some(21));
+    assert_eq!(kv.entry_size(&ktxt("blob")), Some(13 + 8 + 4096));
+    assert_eq!(kv.entry_size(&ktxt("fehlt")), None);
+
+    kv.insert(ktxt("n"), OwnedValue::Text("länger".into())).unwrap();
+    assert_eq!(kv.entry_size(&ktxt("n")), Some(13 + 8 + "länger".len()));
+}
+
+#[test]
+fn iter_by_type_filters_on_value_type() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("i1"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("t"), OwnedValue::Text("text".into())).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Bool(true)).unwrap();
+    kv.insert(ktxt("blob"), OwnedValue::Blob(vec![1, 2])).unwrap();
+    kv.insert(ktxt("i2"), OwnedValue::Integer(2)).unwrap();
+
+    let ints: Vec<_> = kv
+        .iter_by_type(ValueType::Integer)
+        .map(|e| (e.key.clone(), e.value))
+        .collect();
+    assert_eq!(
+        ints,
+        vec![(ktxt("i1"), BorrowedValue::Integer(1)), (ktxt("i2"), BorrowedValue::Integer(2))]
+    );
+
+    let texts: Vec<_> = kv.iter_by_type(ValueType::Text).map(|e| e.value).collect();
+    assert_eq!(texts, vec![BorrowedValue::Text("text")]);
+    let bools: Vec<_> = kv.iter_by_type(ValueType::Bool).map(|e| e.value).collect();
+    assert_eq!(bools, vec![BorrowedValue::Bool(true)]);
+    let blobs: Vec<_> = kv.iter_by_type(ValueType::Blob).map(|e| e.value).collect();
+    assert_eq!(blobs, vec![BorrowedValue::Blob(&[1, 2])]);
+}
+
+#[test]
+fn to_bytes_and_from_bytes_roundtrip() {
+    let path = "test_to_bytes.kv";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(-1)).unwrap();
+    kv.insert(kint(2), OwnedValue::Text("zwei".into())).unwrap();
+    kv.insert(ktxt("blob"), OwnedValue::Blob(vec![9; 10])).unwrap();
+    kv.insert(ktxt("a"), OwnedValue::Bool(true)).unwrap();
+
+    let bytes = kv.to_bytes();
+    kv.persist_to_file(path).unwrap();
+    assert_eq!(std::fs::read(path).unwrap(), bytes);
+
+    let copy = KvStore::from_bytes(&bytes).unwrap();
+    assert_eq!(copy.to_bytes(), bytes);
+    assert_eq!(
+        copy.entries_owned().collect::<Vec<_>>(),
+        kv.entries_owned().collect::<Vec<_>>()
+    );
+
+    assert_eq!(KvStore::from_bytes(&[]).unwrap().keys().count(), 0);
+    assert!(matches!(
+        KvStore::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(KvError::UnexpectedEof { .. })
+    ));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn to_bytes_and_from_bytes_roundtrip_with_seed_and_checksum() {
+    let mut seeded = KvStore::with_checksum_seed(9);
+    seeded.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    seeded.insert(kint(2), OwnedValue::Text("zwei".into())).unwrap();
+
+    let bytes = seeded.to_bytes();
+    let copy = KvStore::from_bytes_with_seed(&bytes, 9).unwrap();
+    assert_eq!(copy.to_bytes(), bytes);
+    assert!(matches!(KvStore::from_bytes(&bytes), Err(KvError::ChecksumSeedMismatch)));
+
+    let mut castagnoli = KvStore::with_checksum(Checksum::Crc32Castagnoli);
+    castagnoli.insert(ktxt("b"), OwnedValue::Bool(true)).unwrap();
+
+    let bytes = castagnoli.to_bytes();
+    let copy = KvStore::from_bytes_with_checksum(&bytes, Checksum::Crc32Castagnoli).unwrap();
+    assert_eq!(copy.to_bytes(), bytes);
+    assert_eq!(copy.get_owned(&ktxt("b")).unwrap(), Some(OwnedValue::Bool(true)));
+}
+
+#[test]
+fn insertion_order_survives_overwrites_and_compaction() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("c"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("a"), OwnedValue::Integer(2)).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Integer(3)).unwrap();
+
+    // Überschreiben: Position bleibt, auch wenn der Wert jetzt am Ende der Daten liegt
+    kv.insert(ktxt("c"), OwnedValue::Text("zehn".into())).unwrap();
+    // Löschen und neu anlegen: wandert ans Ende
+    kv.delete(&ktxt("a"));
+    kv.insert(ktxt("a"), OwnedValue::Integer(20)).unwrap();
+
+    let order = |kv: &KvStore| -> Vec<Key> {
+        kv.iter_insertion_order().map(|e| e.key.clone()).collect()
+    };
+    let expected = vec![ktxt("c"), ktxt("b"), ktxt("a")];
+    assert_eq!(order(&kv), expected);
+    assert_eq!(kv.keys().cloned().collect::<Vec<_>>(), expected);
+
+    kv.compact().unwrap();
+    assert_eq!(order(&kv), expected);
+    assert_eq!(kv.get_borrowed(&ktxt("c")).unwrap(), Some(BorrowedValue::Text("zehn")));
+
+    let reloaded = KvStore::from_bytes(&kv.to_bytes()).unwrap();
+    assert_eq!(order(&reloaded), expected);
+}
+
+#[test]
+fn namespaces_are_isolated() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("name"), OwnedValue::Text("global".into())).unwrap();
+    kv.insert(kint(1), OwnedValue::Integer(1)).unwrap();
+
+    {
+        let mut users = kv.namespace("users");
+        users.insert("name", OwnedValue::Text("anna".into())).unwrap();
+        users.insert("alter", OwnedValue::Integer(30)).unwrap();
+    }
+    {
+        let mut config = kv.namespace("config");
+        assert_eq!(config.get("name").unwrap(), None);
+        config.insert("name", OwnedValue::Text("prod".into())).unwrap();
+        assert!(!config.contains_key("alter"));
+    }
+
+    let users = kv.namespace("users");
+    assert_eq!(users.get("name").unwrap(), Some(BorrowedValue::Text("anna")));
+    let entries: Vec<_> = users.iter().collect();
+    assert_eq!(
+        entries,
+        vec![("name", BorrowedValue::Text("anna")), ("alter", BorrowedValue::Integer(30))]
+    );
+
+    let mut config = kv.namespace("config");
+    assert_eq!(config.get_owned("name").unwrap(), Some(OwnedValue::Text("prod".into())));
+    assert!(config.delete("name"));
+    assert!(!config.delete("name"));
+    assert_eq!(config.iter().count(), 0);
+
+    // Schlüssel außerhalb der Namespaces bleiben unberührt
+    assert_eq!(kv.get_borrowed(&ktxt("name")).unwrap(), Some(BorrowedValue::Text("global")));
+    assert_eq!(kv.get_borrowed(&ktxt("users:name")).unwrap(), Some(BorrowedValue::Text("anna")));
+    assert_eq!(kv.keys().count(), 4);
+}
+
+#[test]
+#[should_panic(expected = "must not contain")]
+fn namespace_name_rejects_separator() {
+    let mut kv = KvStore::new();
+    kv.namespace("a:b");
+}
+
+#[test]
+fn delete_prefix_removes_only_matching_text_keys() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("session:1"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("user:1"), OwnedValue::Integer(2)).unwrap();
+    kv.insert(ktxt("session:2"), OwnedValue::Integer(3)).unwrap();
+    kv.insert(ktxt("sessions"), OwnedValue::Integer(4)).unwrap();
+    kv.insert(kint(1), OwnedValue::Integer(5)).unwrap();
+
+    assert_eq!(kv.delete_prefix("session:"), 2);
+    assert_eq!(
+        kv.keys().cloned().collect::<Vec<_>>(),
+        vec![ktxt("user:1"), ktxt("sessions"), kint(1)]
+    );
+
+    assert_eq!(kv.delete_prefix("nichts:"), 0);
+    // Leerer Präfix trifft alle Text-Schlüssel, aber keine Integer-Schlüssel
+    assert_eq!(kv.delete_prefix(""), 2);
+    assert_eq!(kv.keys().cloned().collect::<Vec<_>>(), vec![kint(1)]);
+}
+
+#[test]
+fn fixed_size_overwrites_happen_in_place() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("zähler"), OwnedValue::Integer(0)).unwrap();
+    kv.insert(ktxt("flag"), OwnedValue::Bool(false)).unwrap();
+    let len = kv.storage_len();
+
+    for i in 1..=10_000 {
+        let previous = kv.insert(ktxt("zähler"), OwnedValue::Integer(i)).unwrap();
+        assert_eq!(previous, Some(OwnedValue::Integer(i - 1)));
+    }
+    kv.insert(ktxt("flag"), OwnedValue::Bool(true)).unwrap();
+
+    assert_eq!(kv.storage_len(), len);
+    assert_eq!(kv.stats().dead_bytes, 0);
+    assert_eq!(kv.get_borrowed(&ktxt("zähler")).unwrap(), Some(BorrowedValue::Integer(10_000)));
+    assert_eq!(kv.get_borrowed(&ktxt("flag")).unwrap(), Some(BorrowedValue::Bool(true)));
+
+    // Anderer Typ gleicher Größe (leerer Text: nur das 8-Byte-Längenfeld) wird angehängt
+    kv.insert(ktxt("zähler"), OwnedValue::Text(String::new())).unwrap();
+    assert_eq!(kv.storage_len(), len + 21);
+    assert_eq!(kv.get_borrowed(&ktxt("zähler")).unwrap(), Some(BorrowedValue::Text("")));
+
+    let reloaded = KvStore::from_bytes(&kv.to_bytes()).unwrap();
+    assert_eq!(reloaded.get_borrowed(&ktxt("flag")).unwrap(), Some(BorrowedValue::Bool(true)));
+}
+
+#[test]
+fn empty_text_and_blob_roundtrip() {
+    let path = "test_empty_values.kv";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("text"), OwnedValue::Text(String::new())).unwrap();
+    kv.insert(ktxt("blob"), OwnedValue::Blob(Vec::new())).unwrap();
+    kv.insert(ktxt(""), OwnedValue::Text(String::new())).unwrap();
+    kv.insert(ktxt("danach"), OwnedValue::Integer(1)).unwrap();
+
+    assert_eq!(kv.get_borrowed(&ktxt("text")).unwrap(), Some(BorrowedValue::Text("")));
+    assert_eq!(kv.get_borrowed(&ktxt("blob")).unwrap(), Some(BorrowedValue::Blob(&[])));
+    assert_eq!(kv.get_owned(&ktxt("")).unwrap(), Some(OwnedValue::Text(String::new())));
+    // Header + 8-Byte-Längenfeld, keine Nutzdaten
+    assert_eq!(kv.entry_size(&ktxt("text")), Some(21));
+    assert_eq!(kv.entry_size(&ktxt("blob")), Some(21));
+
+    kv.persist_to_file(path).unwrap();
+    let loaded = KvStore::load_from_file(path).unwrap();
+    assert_eq!(loaded.keys().count(), 4);
+    assert_eq!(loaded.get_borrowed(&ktxt("text")).unwrap(), Some(BorrowedValue::Text("")));
+    assert_eq!(loaded.get_borrowed(&ktxt("blob")).unwrap(), Some(BorrowedValue::Blob(&[])));
+    assert_eq!(loaded.get_borrowed(&ktxt("")).unwrap(), Some(BorrowedValue::Text("")));
+    assert_eq!(loaded.get_borrowed(&ktxt("danach")).unwrap(), Some(BorrowedValue::Integer(1)));
+
+    let mut out = vec![7];
+    assert_eq!(loaded.read_blob_into(&ktxt("blob"), &mut out).unwrap(), Some(0));
+    assert_eq!(out, vec![7]);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn atomic_persist_replaces_file_without_leftovers() {
+    let path = "test_atomic_persist.kv";
+    let tmp = "test_atomic_persist.kv.tmp";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.persist_to_file_atomic(path).unwrap();
+
+    kv.insert(ktxt("b"), OwnedValue::Integer(2)).unwrap();
+    kv.persist_to_file_atomic(path).unwrap();
+
+    assert!(!std::path::Path::new(tmp).exists());
+    assert_eq!(std::fs::read(path).unwrap(), kv.to_bytes());
+
+    let dir = "test_atomic_persist_dir";
+    let _ = std::fs::create_dir(dir);
+    assert!(matches!(kv.persist_to_file_atomic(dir), Err(KvError::IsDirectory(_))));
+    let _ = std::fs::remove_dir(dir);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn validator_accepts_and_rejects_inserts() {
+    let mut kv = KvStore::new();
+    kv.set_validator(|key, value| match (key, value) {
+        (Key::Text(k), OwnedValue::Integer(_)) if k.starts_with("count:") => Ok(()),
+        (Key::Text(k), _) if k.starts_with("count:") => Err(format!("{k} muss ein Integer sein")),
+        _ => Ok(()),
+    });
+
+    kv.insert(ktxt("count:visits"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("name"), OwnedValue::Text("frei".into())).unwrap();
+
+    let err = kv.insert(ktxt("count:visits"), OwnedValue::Text("viele".into())).unwrap_err();
+    match err {
+        KvError::ValidationFailed(msg) => assert_eq!(msg, "count:visits muss ein Integer sein"),
+        other => panic!("unexpected error {other:?}"),
+    }
+    // Abgelehnte Werte ändern nichts
+    assert_eq!(kv.get_borrowed(&ktxt("count:visits")).unwrap(), Some(BorrowedValue::Integer(1)));
+
+    // Laden prüft nicht: vorhandene Daten bleiben lesbar
+    let mut raw = KvStore::new();
+    raw.insert(ktxt("count:alt"), OwnedValue::Bool(true)).unwrap();
+    let loaded = KvStore::from_bytes(&raw.to_bytes()).unwrap();
+    assert_eq!(loaded.get_borrowed(&ktxt("count:alt")).unwrap(), Some(BorrowedValue::Bool(true)));
+
+    // Klone übernehmen den Validator
+    let mut copy = kv.clone();
+    assert!(copy.insert(ktxt("count:x"), OwnedValue::Bool(false)).is_err());
+    copy.clear_validator();
+    assert!(copy.insert(ktxt("count:x"), OwnedValue::Bool(false)).is_ok());
+}
+
+#[test]
+fn diff_and_apply_roundtrip() {
+    let mut base = KvStore::new();
+    base.insert(ktxt("gleich"), OwnedValue::Integer(1)).unwrap();
+    base.insert(ktxt("geändert"), OwnedValue::Text("alt".into())).unwrap();
+    base.insert(ktxt("weg"), OwnedValue::Bool(true)).unwrap();
+
+    let mut derived = base.clone();
+    derived.insert(ktxt("geändert"), OwnedValue::Text("neu".into())).unwrap();
+    derived.delete(&ktxt("weg"));
+    derived.insert(kint(7), OwnedValue::Blob(vec![7])).unwrap();
+
+    let changes = derived.diff(&base).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            Change::Modified(ktxt("geändert"), OwnedValue::Text("neu".into())),
+            Change::Added(kint(7), OwnedValue::Blob(vec![7])),
+            Change::Removed(ktxt("weg")),
+        ]
+    );
+
+    base.apply(&changes).unwrap();
+    let sorted = |kv: &KvStore| -> Vec<(Key, OwnedValue)> {
+        let mut entries: Vec<_> = kv.entries_owned().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    };
+    assert_eq!(sorted(&base), sorted(&derived));
+    assert!(derived.diff(&base).unwrap().is_empty());
+}
+
+#[test]
+fn verify_value_bytes_checks_single_entry() {
+    let mut store = KvStore::new();
+    store.insert(ktxt("a"), OwnedValue::Text("hallo".into())).unwrap();
+    let raw = store.raw_entry(&ktxt("a")).unwrap().to_vec();
+
+    assert!(verify_value_bytes(&raw).is_ok());
+
+    // Ein Byte im Payload kippen
+    let mut corrupted = raw.clone();
+    *corrupted.last_mut().unwrap() ^= 0xFF;
+    assert!(matches!(
+        verify_value_bytes(&corrupted),
+        Err(KvError::Corrupted(DecodeError::ChecksumMismatch { .. }))
+    ));
+
+    // Abgeschnittener Puffer
+    assert!(matches!(
+        verify_value_bytes(&raw[..raw.len() - 1]),
+        Err(KvError::Corrupted(DecodeError::EntryTruncated))
+    ));
+    assert!(matches!(
+        verify_value_bytes(&raw[..4]),
+        Err(KvError::Corrupted(DecodeError::SliceTooShortForHeader))
+    ));
+}
+
+#[test]
+fn load_strict_rejects_duplicate_keys() {
+    let path = "test_load_strict_duplicates.bin";
+    let _ = std::fs::remove_file(path);
+
+    // Datei von Hand mit doppeltem Schlüssel schreiben
+    let items = vec![
+        (ktxt("a"), OwnedValue::Integer(1)),
+        (ktxt("b"), OwnedValue::Integer(2)),
+        (ktxt("a"), OwnedValue::Integer(3)),
+    ];
+    KvStore::write_entries_to_file(path, items).unwrap();
+
+    match KvStore::load_strict(path) {
+        Err(KvError::DuplicateKey(key)) => assert_eq!(key, ktxt("a")),
+        other => panic!("expected DuplicateKey, got {:?}", other.map(|s| s.keys().count())),
+    }
+
+    // Ohne strict gewinnt der letzte Eintrag
+    let store = KvStore::load_from_file(path).unwrap();
+    assert_eq!(store.get_owned(&ktxt("a")).unwrap(), Some(OwnedValue::Integer(3)));
+    assert_eq!(store.keys().count(), 2);
+
+    // Eindeutige Schlüssel laden auch strict
+    let mut unique = KvStore::new();
+    unique.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    unique.insert(kint(7), OwnedValue::Bool(true)).unwrap();
+    unique.persist_to_file(path).unwrap();
+    let loaded = KvStore::load_strict(path).unwrap();
+    assert_eq!(loaded.get_owned(&kint(7)).unwrap(), Some(OwnedValue::Bool(true)));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn load_from_file_readonly_reads_everything() {
+    let path = "test_readonly_store.bin";
+    let mut store = KvStore::new();
+    store.insert(ktxt("name"), OwnedValue::Text("k9".into())).unwrap();
+    store.insert(kint(1), OwnedValue::Integer(42)).unwrap();
+    store.persist_to_file(path).unwrap();
+
+    // Nur Lesezugriff; insert/delete gibt es auf dem Typ nicht
+    let ro = KvStore::load_from_file_readonly(path).unwrap();
+    assert_eq!(ro.len(), 2);
+    assert!(!ro.is_empty());
+    assert_eq!(ro.get_borrowed(&ktxt("name")).unwrap(), Some(BorrowedValue::Text("k9")));
+    assert_eq!(ro.get_owned(&kint(1)).unwrap(), Some(OwnedValue::Integer(42)));
+    assert_eq!(ro.keys().cloned().collect::<Vec<_>>(), vec![ktxt("name"), kint(1)]);
+    assert_eq!(ro.values().count(), 2);
+    assert_eq!(ro.iter().count(), 2);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn append_to_file_writes_only_new_changes() {
+    let path = "test_append_to_file.bin";
+    let _ = std::fs::remove_file(path);
+
+    let mut store = KvStore::new();
+    store.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    store.insert(ktxt("b"), OwnedValue::Text("alt".into())).unwrap();
+    let seq = store.checkpoint_to_file(path).unwrap();
+    assert_eq!(seq, store.seq());
+    let snapshot = std::fs::read(path).unwrap();
+
+    // Ändern, löschen, neu einfügen
+    store.insert(ktxt("b"), OwnedValue::Text("neu".into())).unwrap();
+    store.delete(&ktxt("a"));
+    store.insert(kint(3), OwnedValue::Bool(true)).unwrap();
+    let seq = store.append_to_file(path, seq).unwrap();
+    assert_eq!(seq, store.seq());
+
+    // Die alten Einträge bleiben unverändert am Anfang der Datei
+    let bytes = std::fs::read(path).unwrap();
+    assert!(bytes.len() > snapshot.len());
+    assert_eq!(&bytes[..snapshot.len()], &snapshot[..]);
+    let after_first = bytes.len() as u64;
+
+    let loaded = KvStore::load_from_file(path).unwrap();
+    assert_eq!(loaded.get_owned(&ktxt("a")).unwrap(), None);
+    assert_eq!(loaded.get_owned(&ktxt("b")).unwrap(), Some(OwnedValue::Text("neu".into())));
+    assert_eq!(loaded.get_owned(&kint(3)).unwrap(), Some(OwnedValue::Bool(true)));
+
+    // Ohne neue Änderungen wird nichts angehängt
+    assert_eq!(store.append_to_file(path, seq).unwrap(), seq);
+    assert_eq!(std::fs::metadata(path).unwrap().len(), after_first);
+
+    store.insert(ktxt("a"), OwnedValue::Integer(7)).unwrap();
+    store.append_to_file(path, seq).unwrap();
+    let loaded = KvStore::load_from_file(path).unwrap();
+    assert_eq!(loaded.diff(&store).unwrap(), vec![]);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn append_to_file_writes_header_for_new_file() {
+    let path = "test_append_to_file_header.bin";
+    let _ = std::fs::remove_file(path);
+
+    let mut store = KvStore::with_endianness(Endianness::Big);
+    store.insert(ktxt("x"), OwnedValue::Integer(-5)).unwrap();
+    store.append_to_file(path, 0).unwrap();
+
+    let loaded = KvStore::load_from_file(path).unwrap();
+    assert_eq!(loaded.endianness(), Endianness::Big);
+    assert_eq!(loaded.get_owned(&ktxt("x")).unwrap(), Some(OwnedValue::Integer(-5)));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn load_reports_length_past_end_instead_of_dropping_the_rest() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("b"), OwnedValue::Text("zwei".into())).unwrap();
+    kv.insert(ktxt("c"), OwnedValue::Integer(3)).unwrap();
+    let bytes = kv.to_bytes();
+
+    // Erster Wert beginnt nach dem Schlüssel "a" (Header, Textlänge, ein Byte)
+    let value_off = 13 + 8 + 1;
+    let value = kv.raw_entry(&ktxt("a")).unwrap();
+    assert_eq!(&bytes[value_off..value_off + value.len()], value);
+
+    // Sein Längenfeld zeigt hinter das Pufferende
+
+    let mut past_end = bytes.clone();
+    past_end[value_off..value_off + 8].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+    assert!(matches!(KvStore::from_bytes(&past_end), Err(KvError::UnexpectedEof { offset: 0 })));
+
+    // Unmöglich große Länge ist Korruption
+    let mut huge = bytes.clone();
+    huge[value_off..value_off + 8].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+    assert!(matches!(
+        KvStore::from_bytes(&huge),
+        Err(KvError::Corrupted(DecodeError::LengthTooLarge(_)))
+    ));
+}
+
+#[test]
+fn serialized_len_matches_written_entry() {
+    let values = [
+        OwnedValue::Integer(-42),
+        OwnedValue::Bool(true),
+        OwnedValue::Text(String::new()),
+        OwnedValue::Text("grüße".into()),
+        OwnedValue::Blob(vec![]),
+        OwnedValue::Blob(vec![7; 300]),
+    ];
+
+    for endianness in [Endianness::Little, Endianness::Big] {
+        let mut store = KvStore::with_endianness(endianness);
+        for (i, value) in values.iter().enumerate() {
+            store.insert(kint(i as i64), value.clone()).unwrap();
+            let written = store.raw_entry(&kint(i as i64)).unwrap().len();
+            assert_eq!(value.serialized_len(), written, "{value:?}");
+        }
+    }
+
+    // Komprimierte Blobs werden höchstens so groß wie angegeben
+    let mut store = KvStore::with_compression(16);
+    let blob = OwnedValue::Blob(vec![0; 4096]);
+    store.insert(ktxt("z"), blob.clone()).unwrap();
+    assert!(store.raw_entry(&ktxt("z")).unwrap().len() < blob.serialized_len());
+}
+
+#[test]
+fn debug_hexdump_annotates_entry_fields() {
+    let mut store = KvStore::new();
+    store.insert(ktxt("a"), OwnedValue::Integer(42)).unwrap();
+
+    let dump = store.debug_hexdump();
+    let lines: Vec<&str> = dump.lines().collect();
+
+    assert_eq!(lines[0], "00000000  entry, key Text(\"a\")");
+    assert_eq!(lines[1], "00000000    length    0d 00 00 00 00 00 00 00  = 13");
+    assert!(lines[2].starts_with("00000008    checksum  "));
+    assert_eq!(lines[3], "0000000c    tag       00                       = Integer");
+    assert_eq!(lines[4], "0000000d    payload   2a 00 00 00 00 00 00 00");
+    assert_eq!(lines.len(), 5);
+
+    // Überschriebene Werte bleiben als tote Einträge sichtbar
+    store.insert(ktxt("a"), OwnedValue::Text("neu".into())).unwrap();
+    let dump = store.debug_hexdump();
+    assert!(dump.starts_with("00000000  entry, dead\n"));
+    assert!(dump.contains("00000015  entry, key Text(\"a\")\n"));
+}
+
+#[test]
+fn timestamp_roundtrip_stays_distinct_from_integer() {
+    let path = "test_timestamp_roundtrip.bin";
+
+    for endianness in [Endianness::Little, Endianness::Big] {
+        let mut kv = KvStore::with_endianness(endianness);
+        kv.insert(ktxt("t"), OwnedValue::Timestamp(0)).unwrap();
+        kv.insert(ktxt("max"), OwnedValue::Timestamp(u64::MAX)).unwrap();
+        kv.insert(ktxt("i"), OwnedValue::Integer(0)).unwrap();
+        kv.persist_to_file(path).unwrap();
+
+        let loaded = KvStore::load_from_file(path).unwrap();
+        assert_eq!(loaded.get_owned(&ktxt("t")).unwrap(), Some(OwnedValue::Timestamp(0)));
+        assert_eq!(loaded.get_borrowed(&ktxt("max")).unwrap(), Some(BorrowedValue::Timestamp(u64::MAX)));
+        assert_eq!(loaded.type_of(&ktxt("t")).unwrap(), Some(ValueType::Timestamp));
+        assert_eq!(loaded.type_of(&ktxt("i")).unwrap(), Some(ValueType::Integer));
+        assert_ne!(loaded.get_owned(&ktxt("t")).unwrap(), loaded.get_owned(&ktxt("i")).unwrap());
+    }
+
+    // Zeitstempel sind keine gültigen Schlüssel
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("x"), OwnedValue::Integer(1)).unwrap();
+    let mut bytes = kv.to_bytes();
+    bytes[12] = 6; // Tag des Schlüssels auf Timestamp
+    assert!(KvStore::from_bytes(&bytes).is_err());
+
+    assert_eq!(OwnedValue::Timestamp(1_700_000_000).to_string(), "2023-11-14T22:13:20Z");
+    assert_eq!(OwnedValue::Timestamp(0).to_string(), "1970-01-01T00:00:00Z");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn load_handles_files_shorter_than_one_entry() {
+    let path = "test_load_short_files.bin";
+
+    // Leere Datei ergibt einen leeren Store
+    std::fs::write(path, b"").unwrap();
+    assert_eq!(KvStore::load_from_file(path).unwrap().keys().count(), 0);
+
+    // Ein Byte, bzw. ein Byte weniger als ein Eintrags-Header
+    for len in [1, 12] {
+        std::fs::write(path, vec![0u8; len]).unwrap();
+        let res = KvStore::load_from_file(path);
+        assert!(matches!(res, Err(KvError::UnexpectedEof { offset: 0 })), "len={len}: {:?}", res.err());
+    }
+
+    // Dateikopf ohne Einträge ist ein leerer Store, ein abgeschnittener Kopf nicht
+    let mut kv = KvStore::with_endianness(Endianness::Big);
+    kv.persist_to_file(path).unwrap();
+    let header = std::fs::read(path).unwrap();
+    assert_eq!(header.len(), 7);
+    assert_eq!(KvStore::load_from_file(path).unwrap().keys().count(), 0);
+
+    std::fs::write(path, &header[..6]).unwrap();
+    assert!(matches!(KvStore::load_from_file(path), Err(KvError::UnexpectedEof { offset: 0 })));
+
+    // Nach dem Kopf zählt der Offset ab Dateianfang
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.persist_to_file(path).unwrap();
+    let mut bytes = std::fs::read(path).unwrap();
+    bytes.push(0);
+    let full_len = bytes.len() - 1;
+    std::fs::write(path, &bytes).unwrap();
+    assert!(matches!(
+        KvStore::load_from_file(path),
+        Err(KvError::UnexpectedEof { offset }) if offset == full_len
+    ));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn get_or_insert_with_only_computes_on_miss() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("da"), OwnedValue::Integer(1)).unwrap();
+
+    let mut calls = 0;
+    let value = kv
+        .get_or_insert_with(ktxt("da"), || {
+            calls += 1;
+            OwnedValue::Integer(99)
+        })
+        .unwrap();
+    assert_eq!(value, BorrowedValue::Integer(1));
+    assert_eq!(calls, 0);
+
+    let value = kv
+        .get_or_insert_with(ktxt("neu"), || {
+            calls += 1;
+            OwnedValue::Text("berechnet".into())
+        })
+        .unwrap();
+    assert_eq!(value, BorrowedValue::Text("berechnet"));
+    assert_eq!(calls, 1);
+
+    // Beim zweiten Zugriff ist der Wert schon da
+    let value = kv
+        .get_or_insert_with(ktxt("neu"), || {
+            calls += 1;
+            OwnedValue::Text("nochmal".into())
+        })
+        .unwrap();
+    assert_eq!(value, BorrowedValue::Text("berechnet"));
+    assert_eq!(calls, 1);
+    assert_eq!(kv.keys().count(), 2);
+
+    // Fehler aus insert (z.B. der Validator) werden weitergegeben
+    kv.set_validator(|_, _| Err("nein".into()));
+    assert!(matches!(
+        kv.get_or_insert_with(ktxt("abgelehnt"), || OwnedValue::Bool(true)),
+        Err(KvError::ValidationFailed(_))
+    ));
+    assert!(!kv.contains_key(&ktxt("abgelehnt")));
+}
+
+#[test]
+fn load_reports_invalid_key_type_for_blob_keys() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    let mut bytes = kv.to_bytes();
+
+    // Tag des Schlüssels von Text auf Blob; der Payload bleibt gültig
+    bytes[12] = 3;
+    assert!(matches!(KvStore::from_bytes(&bytes), Err(KvError::InvalidKeyType)));
+}
+
+#[test]
+fn corruption_kind_classifies_errors() {
+    let mut store = KvStore::new();
+    store.insert(ktxt("a"), OwnedValue::Text("hallo".into())).unwrap();
+    let raw = store.raw_entry(&ktxt("a")).unwrap().to_vec();
+    let kind = |bytes: &[u8]| verify_value_bytes(bytes).unwrap_err().corruption_kind();
+
+    let mut flipped = raw.clone();
+    *flipped.last_mut().unwrap() ^= 0xFF;
+    assert_eq!(kind(&flipped), Some(CorruptionKind::ChecksumMismatch));
+    assert_eq!(kind(&raw[..raw.len() - 1]), Some(CorruptionKind::Truncated));
+    assert_eq!(kind(&raw[..4]), Some(CorruptionKind::Truncated));
+
+    // Längenfeld (erste 8 Bytes) auf unmögliche Werte setzen
+    let mut bad_len = raw.clone();
+    bad_len[..8].copy_from_slice(&2u64.to_le_bytes());
+    assert_eq!(kind(&bad_len), Some(CorruptionKind::InvalidLength));
+    bad_len[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert_eq!(kind(&bad_len), Some(CorruptionKind::InvalidLength));
+
+    // Abgeschnittene Datei
+    let bytes = store.to_bytes();
+    let err = KvStore::from_bytes(&bytes[..bytes.len() - 3]).err().unwrap();
+    assert_eq!(err.corruption_kind(), Some(CorruptionKind::Truncated));
+
+    // Varianten, die sich nur mit passender Prüfsumme erzeugen lassen
+    let cases = [
+        (DecodeError::UnknownTypeTag(42), CorruptionKind::UnknownTypeTag),
+        (DecodeError::InvalidUtf8, CorruptionKind::MalformedPayload),
+        (DecodeError::MissingIntegerPayload, CorruptionKind::MalformedPayload),
+        (DecodeError::UnsupportedFileVersion(9), CorruptionKind::UnsupportedFormat),
+        (DecodeError::UnknownEndianness(7), CorruptionKind::UnsupportedFormat),
+    ];
+    for (e, expected) in cases {
+        assert_eq!(KvError::Corrupted(e).corruption_kind(), Some(expected));
+    }
+    assert_eq!(
+        KvError::FileChecksumMismatch { computed: 1, stored: 2 }.corruption_kind(),
+        Some(CorruptionKind::ChecksumMismatch)
+    );
+    assert_eq!(KvError::InvalidKeyType.corruption_kind(), Some(CorruptionKind::InvalidKeyType));
+
+    // Keine Beschädigung
+    assert_eq!(KvError::NoteNotFound(1).corruption_kind(), None);
+    assert_eq!(KvError::IdSpaceExhausted.corruption_kind(), None);
+}
+
+#[test]
+fn write_entry_and_read_entry_round_trip() {
+    let pairs = vec![
+        (ktxt("text"), OwnedValue::Text("hallo".into())),
+        (kint(-7), OwnedValue::Integer(42)),
+        (ktxt("blob"), OwnedValue::Blob(vec![0, 1, 2, 255])),
+        (ktxt("flag"), OwnedValue::Bool(false)),
+    ];
+
+    let mut stream = Vec::new();
+    for (key, value) in &pairs {
+        write_entry(key, value, &mut stream).unwrap();
+    }
+
+    // Gleiches Layout wie ein Datensatz in persist_to_file
+    let mut store = KvStore::new();
+    store.insert(ktxt("text"), OwnedValue::Text("hallo".into())).unwrap();
+    let persisted = store.to_bytes();
+    let mut single = Vec::new();
+    write_entry(&pairs[0].0, &pairs[0].1, &mut single).unwrap();
+    assert!(persisted.ends_with(&single));
+
+    let mut cursor = std::io::Cursor::new(stream);
+    for (key, value) in &pairs {
+        let (k, v) = read_entry(&mut cursor).unwrap().unwrap();
+        assert_eq!(&k, key);
+        assert_eq!(&v, value);
+    }
+    // Sauberes Ende
+    assert!(read_entry(&mut cursor).unwrap().is_none());
+    assert!(read_entry(&mut cursor).unwrap().is_none());
+}
+
+#[test]
+fn read_entry_reports_truncated_and_corrupted_pairs() {
+    let mut stream = Vec::new();
+    write_entry(&ktxt("a"), &OwnedValue::Text("wert".into()), &mut stream).unwrap();
+
+    // Mitten im Header, im Payload und zwischen Schlüssel und Wert abgeschnitten
+    let key_len = 13 + 8 + 1;
+    for cut in [5, key_len - 1, key_len, stream.len() - 1] {
+        let mut cursor = std::io::Cursor::new(&stream[..cut]);
+        assert!(matches!(
+            read_entry(&mut cursor),
+            Err(KvError::UnexpectedEof { offset: 0 })
+        ));
+    }
+
+    let mut flipped = stream.clone();
+    *flipped.last_mut().unwrap() ^= 0xFF;
+    assert!(matches!(
+        read_entry(&mut std::io::Cursor::new(flipped)),
+        Err(KvError::Corrupted(DecodeError::ChecksumMismatch { .. }))
+    ));
+
+    // Bool als Schlüssel
+    let mut bad_key = Vec::new();
+    write_entry(&ktxt("x"), &OwnedValue::Bool(true), &mut bad_key).unwrap();
+    let value_only = bad_key[key_len..].to_vec();
+    let mut pair = value_only.clone();
+    pair.extend_from_slice(&value_only);
+    assert!(matches!(
+        read_entry(&mut std::io::Cursor::new(pair)),
+        Err(KvError::InvalidKeyType)
+    ));
+}
+
+#[test]
+fn transaction_commits_all_writes() {
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("alt"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(ktxt("zähler"), OwnedValue::Integer(1)).unwrap();
+
+    kv.transaction(|txn: &mut Txn| {
+        txn.insert(ktxt("neu"), OwnedValue::Text("hallo".into()))?;
+        txn.insert(ktxt("zähler"), OwnedValue::Integer(2))?;
+        txn.delete(&ktxt("alt"));
+        // Gepufferte Schreibvorgänge sind innerhalb der Transaktion sichtbar
+        assert_eq!(txn.get_owned(&ktxt("zähler"))?, Some(OwnedValue::Integer(2)));
+        assert_eq!(txn.get_owned(&ktxt("alt"))?, None);
+        Ok(())
+    })
+    .unwrap();
+
+    assert_eq!(kv.get_borrowed(&ktxt("neu")).unwrap(), Some(BorrowedValue::Text("hallo")));
+    assert_eq!(kv.get_borrowed(&ktxt("zähler")).unwrap(), Some(BorrowedValue::Integer(2)));
+    assert!(!kv.contains_key(&ktxt("alt")));
+    assert_eq!(kv.keys().count(), 2);
+}
+
+#[test]
+fn transaction_rolls_back_on_validation_failure() {
+    let mut kv = KvStore::new();
+    kv.set_validator(|_key, value| match value {
+        OwnedValue::Integer(i) if *i < 0 => Err("negativ".to_string()),
+        _ => Ok(()),
+    });
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    let before = kv.to_bytes();
+    let seq = kv.seq();
+
+    let res = kv.transaction(|txn| {
+        txn.insert(ktxt("a"), OwnedValue::Integer(2))?;
+        txn.delete(&ktxt("a"));
+        txn.insert(ktxt("b"), OwnedValue::Integer(3))?;
+        txn.insert(ktxt("c"), OwnedValue::Integer(-1))?;
+        txn.insert(ktxt("d"), OwnedValue::Integer(4))?;
+        Ok(())
+    });
+    assert!(matches!(res, Err(KvError::ValidationFailed(_))));
+
+    // Keine Teilschreibvorgänge
+    assert_eq!(kv.to_bytes(), before);
+    assert_eq!(kv.seq(), seq);
+    assert_eq!(kv.get_borrowed(&ktxt("a")).unwrap(), Some(BorrowedValue::Integer(1)));
+    assert!(!kv.contains_key(&ktxt("b")));
+
+    // Fehler aus der Closure selbst verwerfen ebenfalls alles
+    let res = kv.transaction(|txn| {
+        txn.insert(ktxt("b"), OwnedValue::Integer(3))?;
+        Err(KvError::NoteNotFound(0))
+    });
+    assert!(res.is_err());
+    assert_eq!(kv.to_bytes(), before);
+}
+
+#[test]
+fn interned_keys_behave_like_text_keys() {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    let interned = Key::Interned(Arc::from("a"));
+    assert_eq!(interned, ktxt("a"));
+    assert_ne!(interned, ktxt("b"));
+    assert!(kint(i64::MAX) < interned && interned < ktxt("b"));
+    assert!(HashSet::from([ktxt("a")]).contains(&interned));
+    assert_eq!(interned.as_text(), Some("a"));
+    assert_eq!(kint(1).as_text(), None);
+
+    let path = "test_interned_keys.bin";
+    let mut kv = KvStore::with_key_interning();
+    kv.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+    kv.insert(kint(2), OwnedValue::Integer(2)).unwrap();
+    kv.insert(interned.clone(), OwnedValue::Integer(3)).unwrap();
+
+    // Ein Schlüssel, abrufbar in beiden Formen
+    assert_eq!(kv.keys().count(), 2);
+    assert!(matches!(kv.keys().next(), Some(Key::Interned(_))));
+    assert_eq!(kv.get_owned(&ktxt("a")).unwrap(), Some(OwnedValue::Integer(3)));
+    assert_eq!(kv.get_owned(&interned).unwrap(), Some(OwnedValue::Integer(3)));
+
+    // Auf der Platte sind es gewöhnliche Text-Schlüssel
+    kv.persist_to_file(path).unwrap();
+    let loaded = KvStore::load_from_file(path).unwrap();
+    assert!(matches!(loaded.keys().next(), Some(Key::Text(_))));
+    assert!(loaded.diff(&kv).unwrap().is_empty());
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "mmap")]
+
+use kv_store::{BorrowedValue, DecodeError, Endianness, Key, KvError, KvStore, OwnedValue};
+
+fn ktxt(s: &str) -> Key {
+    Key::Text(s.to_string())
+}
+
+#[test]
+fn test_mmap_reads_persisted_values() {
+    let path = "test_mmap_read.bin";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("int"), OwnedValue::Integer(-3)).unwrap();
+    kv.insert(ktxt("bool"), OwnedValue::Bool(true)).unwrap();
+    kv.insert(ktxt("text"), OwnedValue::Text("gemappt".into())).unwrap();
+    kv.insert(Key::Integer(9), OwnedValue::Blob(vec![1, 2, 3])).unwrap();
+    kv.insert(ktxt("int"), OwnedValue::Integer(4)).unwrap();
+    kv.persist_to_file(path).unwrap();
+
+    let store = KvStore::open_mmap(path).unwrap();
+    assert_eq!(store.len(), 4);
+
+    assert_eq!(store.get_borrowed(&ktxt("int")).unwrap(), Some(BorrowedValue::Integer(4)));
+    assert_eq!(store.get_borrowed(&ktxt("bool")).unwrap(), Some(BorrowedValue::Bool(true)));
+    assert_eq!(store.get_borrowed(&ktxt("text")).unwrap(), Some(BorrowedValue::Text("gemappt")));
+    assert_eq!(
+        store.get_borrowed(&Key::Integer(9)).unwrap(),
+        Some(BorrowedValue::Blob(&[1, 2, 3]))
+    );
+    assert_eq!(store.get_borrowed(&ktxt("fehlt")).unwrap(), None);
+
+    // Iteration liefert dieselben Werte wie der In-Memory-Store
+    let mapped: Vec<_> = store.iter().map(|e| (e.key.clone(), e.value.to_owned())).collect();
+    let in_memory: Vec<_> = kv.iter().map(|e| (e.key.clone(), e.value.to_owned())).collect();
+    assert_eq!(mapped, in_memory);
+
+    drop(store);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_mmap_reports_checksum_mismatch() {
+    let path = "test_mmap_corrupt.bin";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::new();
+    kv.insert(ktxt("x"), OwnedValue::Text("intakt".into())).unwrap();
+    kv.persist_to_file(path).unwrap();
+
+    // Letztes Byte gehört zum Text-Payload des Werts
+    let mut bytes = std::fs::read(path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    std::fs::write(path, &bytes).unwrap();
+
+    let store = KvStore::open_mmap(path).unwrap();
+    let res = store.get_borrowed(&ktxt("x"));
+    assert!(matches!(
+        res,
+        Err(KvError::Corrupted(DecodeError::ChecksumMismatch { .. }))
+    ));
+
+    drop(store);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_mmap_reads_big_endian_file() {
+    let path = "test_mmap_big_endian.bin";
+    let _ = std::fs::remove_file(path);
+
+    let mut kv = KvStore::with_endianness(Endianness::Big);
+    kv.insert(ktxt("n"), OwnedValue::Integer(258)).unwrap();
+    kv.insert(ktxt("t"), OwnedValue::Text("groß".into())).unwrap();
+    kv.persist_to_file(path).unwrap();
+
+    let store = KvStore::open_mmap(path).unwrap();
+    assert_eq!(store.get_borrowed(&ktxt("n")).unwrap(), Some(BorrowedValue::Integer(258)));
+    assert_eq!(store.get_borrowed(&ktxt("t")).unwrap(), Some(BorrowedValue::Text("groß")));
+
+    drop(store);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_mmap_skips_deleted_keys() {
+    let path = "test_mmap_tombstones.bin";
+    let _ = std::fs::remove_file(path);
+
+    {
+        let mut log = KvStore::open_appending(path).unwrap();
+        log.insert(ktxt("a"), OwnedValue::Integer(1)).unwrap();
+        log.insert(ktxt("b"), OwnedValue::Integer(2)).unwrap();
+        log.delete(&ktxt("a")).unwrap();
+    }
+
+    let store = KvStore::open_mmap(path).unwrap();
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.get_borrowed(&ktxt("a")).unwrap(), None);
+    assert_eq!(store.get_borrowed(&ktxt("b")).unwrap(), Some(BorrowedValue::Integer(2)));
+
+    drop(store);
+    let _ = std::fs::remove_file(path);
+}
@@ -118,3 +118,816 @@ fn test_persist_and_load() {
     // Cleanup nach dem Test
     let _ = fs::remove_file(test_file);
 }
+
+#[test]
+fn test_links_and_backlinks() {
+    let test_file = "test_notes_links.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).expect("Failed to open store");
+
+    let a = store.create("A".to_string(), "".to_string()).unwrap();
+    let b = store.create("B".to_string(), "".to_string()).unwrap();
+    let c = store.create("C".to_string(), "".to_string()).unwrap();
+
+    store.add_link(a, b).expect("Failed to link a -> b");
+    store.add_link(a, c).expect("Failed to link a -> c");
+    store.add_link(c, b).expect("Failed to link c -> b");
+    // Doppelte Links werden ignoriert
+    store.add_link(a, b).expect("Failed to relink a -> b");
+
+    assert_eq!(store.links_of(a).unwrap(), vec![b, c]);
+    assert_eq!(store.links_of(b).unwrap(), Vec::<u64>::new());
+    assert_eq!(store.backlinks_of(b).unwrap(), vec![a, c]);
+    assert_eq!(store.backlinks_of(a).unwrap(), Vec::<u64>::new());
+
+    // Links auf nicht existierende Notes schlagen fehl
+    assert!(store.add_link(a, 999).is_err());
+    assert!(store.add_link(999, a).is_err());
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_delete_and_unlink_cleans_dangling_links() {
+    let test_file = "test_notes_unlink.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).expect("Failed to open store");
+
+    let a = store.create("A".to_string(), "".to_string()).unwrap();
+    let b = store.create("B".to_string(), "".to_string()).unwrap();
+    store.add_link(a, b).unwrap();
+
+    store.delete_and_unlink(b).expect("Failed to delete note");
+
+    assert!(store.get(b).unwrap().is_none());
+    assert_eq!(store.links_of(a).unwrap(), Vec::<u64>::new());
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_legacy_note_without_links_still_decodes() {
+    use kv_store::notes::note_from_bytes;
+
+    // Layout vor Einführung von `links`: id, title, body, tags, updated_at
+    let legacy = bincode::serialize(&(
+        7u64,
+        "Old".to_string(),
+        "Body".to_string(),
+        vec!["tag".to_string()],
+        0u64,
+    ))
+    .unwrap();
+
+    let note = note_from_bytes(&legacy).expect("Failed to decode legacy note");
+
+    assert_eq!(note.id, 7);
+    assert_eq!(note.title, "Old");
+    assert_eq!(note.tags, vec!["tag".to_string()]);
+    assert!(note.links.is_empty());
+}
+
+#[test]
+fn test_unique_titles_rejects_duplicates() {
+    use kv_store::KvError;
+
+    let test_file = "test_notes_unique_titles.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file)
+        .expect("Failed to open store")
+        .with_unique_titles(true)
+        .expect("Failed to enable unique titles");
+
+    let a = store.create("Einkauf".to_string(), "".to_string()).unwrap();
+    let b = store.create("Urlaub".to_string(), "".to_string()).unwrap();
+
+    // Gleicher Titel (Groß-/Kleinschreibung egal) beim Anlegen
+    let res = store.create("EINKAUF".to_string(), "".to_string());
+    assert!(matches!(res, Err(KvError::DuplicateTitle(_))));
+
+    // Umbenennen in einen bestehenden Titel
+    let mut note_b = store.get(b).unwrap().unwrap();
+    note_b.title = "einkauf".to_string();
+    let res = store.update(note_b);
+    assert!(matches!(res, Err(KvError::DuplicateTitle(_))));
+
+    // Eigener Titel und freie Titel sind erlaubt
+    let mut note_a = store.get(a).unwrap().unwrap();
+    note_a.body = "Milch".to_string();
+    store.update(note_a).expect("Updating own title must succeed");
+
+    let mut note_b = store.get(b).unwrap().unwrap();
+    note_b.title = "Reise".to_string();
+    store.update(note_b).expect("Renaming to a free title must succeed");
+    store.create("Urlaub".to_string(), "".to_string())
+        .expect("Old title of renamed note is free again");
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_unique_titles_rebuilt_on_open() {
+    let test_file = "test_notes_unique_reopen.bin";
+    let _ = fs::remove_file(test_file);
+
+    {
+        let mut store = NoteStore::open(test_file).unwrap();
+        store.create("Idee".to_string(), "".to_string()).unwrap();
+        store.save(test_file).unwrap();
+    }
+
+    let mut store = NoteStore::open(test_file)
+        .unwrap()
+        .with_unique_titles(true)
+        .unwrap();
+    assert!(store.create("idee".to_string(), "".to_string()).is_err());
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_updated_at_is_set_on_create_and_update() {
+    let test_file = "test_notes_updated_at.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let id = store.create("Zeit".to_string(), "".to_string()).unwrap();
+
+    let created = store.get(id).unwrap().unwrap();
+    assert!(created.updated_at > 0);
+
+    // Zeitstempel haben Sekundenauflösung
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    let mut note = created;
+    note.body = "geändert".to_string();
+    let before = note.updated_at;
+    store.update(note).unwrap();
+
+    let updated = store.get(id).unwrap().unwrap();
+    assert!(updated.updated_at > before);
+    assert_eq!(updated.id, id);
+    assert_eq!(updated.title, "Zeit");
+    assert_eq!(updated.body, "geändert");
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_create_with_tags() {
+    let test_file = "test_notes_tags.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let tags = vec![
+        " arbeit ".to_string(),
+        "rust".to_string(),
+        "arbeit".to_string(),
+        "  ".to_string(),
+    ];
+    let id = store.create_with_tags("Sprint".to_string(), "".to_string(), tags).unwrap();
+    store.create("Ohne Tags".to_string(), "".to_string()).unwrap();
+
+    let metas = store.list_meta().unwrap();
+    let meta = metas.iter().find(|m| m.id == id).unwrap();
+    assert_eq!(meta.tags, vec!["arbeit".to_string(), "rust".to_string()]);
+
+    // Gleiche Filterlogik wie die Tag-Suche in der TUI
+    let search_lower = "RUST".to_lowercase();
+    let hits: Vec<u64> = metas
+        .iter()
+        .filter(|m| m.tags.iter().any(|t| t.to_lowercase().contains(&search_lower)))
+        .map(|m| m.id)
+        .collect();
+    assert_eq!(hits, vec![id]);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_find_by_tag() {
+    let test_file = "test_notes_find_by_tag.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let tags = |t: &[&str]| t.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    let a = store.create_with_tags("A".into(), "".into(), tags(&["Rust", "arbeit"])).unwrap();
+    let _b = store.create_with_tags("B".into(), "".into(), tags(&["privat"])).unwrap();
+    let c = store.create_with_tags("C".into(), "".into(), tags(&["rust"])).unwrap();
+    // Teilstring zählt nicht
+    let _d = store.create_with_tags("D".into(), "".into(), tags(&["rustikal"])).unwrap();
+
+    let ids: Vec<u64> = store.find_by_tag("RUST").unwrap().iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec![a, c]);
+
+    assert!(store.find_by_tag("fehlt").unwrap().is_empty());
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_count_and_paged_listing() {
+    let test_file = "test_notes_paged.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    assert_eq!(store.count(), 0);
+    assert!(store.list_meta_paged(0, 10).unwrap().is_empty());
+
+    for i in 0..7 {
+        store.create(format!("Notiz {i}"), "".to_string()).unwrap();
+    }
+    assert_eq!(store.count(), 7);
+
+    let ids = |page: Vec<kv_store::notes::NoteMeta>| page.iter().map(|m| m.id).collect::<Vec<_>>();
+
+    assert_eq!(ids(store.list_meta_paged(0, 3).unwrap()), vec![1, 2, 3]);
+    assert_eq!(ids(store.list_meta_paged(3, 3).unwrap()), vec![4, 5, 6]);
+    // Unvollständige letzte Seite
+    assert_eq!(ids(store.list_meta_paged(6, 3).unwrap()), vec![7]);
+    // Offset hinter dem Ende
+    assert!(store.list_meta_paged(7, 3).unwrap().is_empty());
+    assert!(store.list_meta_paged(100, 3).unwrap().is_empty());
+    assert!(store.list_meta_paged(0, 0).unwrap().is_empty());
+
+    store.delete(2).unwrap();
+    assert_eq!(store.count(), 6);
+    assert_eq!(ids(store.list_meta_paged(0, 3).unwrap()), vec![1, 3, 4]);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_search_matches_body() {
+    let test_file = "test_notes_search.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let a = store.create("Einkauf".into(), "Milch, Brot und KÄSE".into()).unwrap();
+    let b = store.create_with_tags("Rezept".into(), "Nudeln".into(), vec!["käse".into()]).unwrap();
+    let _c = store.create("Urlaub".into(), "Strand".into()).unwrap();
+    let d = store.create("Käsekuchen".into(), "".into()).unwrap();
+
+    // Nur im Body von a, im Tag von b, im Titel von d
+    let ids: Vec<u64> = store.search("käse").unwrap().iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec![a, b, d]);
+
+    let ids: Vec<u64> = store.search("brot").unwrap().iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec![a]);
+
+    assert!(store.search("berge").unwrap().is_empty());
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_rename() {
+    use kv_store::KvError;
+
+    let test_file = "test_notes_rename.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file)
+        .unwrap()
+        .with_unique_titles(true)
+        .unwrap();
+    let id = store.create_with_tags("Alt".into(), "Inhalt".into(), vec!["x".into()]).unwrap();
+    let other = store.create("Anders".into(), "".into()).unwrap();
+
+    assert!(store.rename(id, "Neu".into()).unwrap());
+    let note = store.get(id).unwrap().unwrap();
+    assert_eq!(note.title, "Neu");
+    assert_eq!(note.body, "Inhalt");
+    assert_eq!(note.tags, vec!["x".to_string()]);
+    assert_eq!(store.list_meta().unwrap()[0].title, "Neu");
+
+    // Fehlende Notiz
+    assert!(!store.rename(99, "Egal".into()).unwrap());
+
+    // Kollision mit bestehendem Titel
+    assert!(matches!(
+        store.rename(other, "neu".into()),
+        Err(KvError::DuplicateTitle(_))
+    ));
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_ids_not_reused_after_unsaved_create() {
+    let test_file = "test_notes_id_reuse.bin";
+    let _ = fs::remove_file(test_file);
+
+    {
+        let mut store = NoteStore::open(test_file).unwrap();
+        store.create("Gespeichert".into(), "".into()).unwrap();
+        store.save(test_file).unwrap();
+
+        // Angelegt, aber nie gespeichert
+        store.create("Verloren".into(), "".into()).unwrap();
+    }
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let id = store.create("Nach Neustart".into(), "".into()).unwrap();
+    assert_eq!(id, 2);
+    assert_eq!(store.get(1).unwrap().unwrap().title, "Gespeichert");
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_next_id_skips_existing_notes_when_counter_lags() {
+    use kv_store::notes::{note_to_bytes, Note};
+    use kv_store::{Key, KvStore, OwnedValue};
+
+    let test_file = "test_notes_counter_lag.bin";
+    let _ = fs::remove_file(test_file);
+
+    // Datei mit Notiz 3, aber ohne Zähler (z. B. von einem anderen Werkzeug geschrieben)
+    let note = Note {
+        id: 3,
+        title: "Fremd".into(),
+        body: "".into(),
+        tags: vec![],
+        updated_at: 0,
+        links: vec![],
+    };
+    let mut kv = KvStore::new();
+    kv.insert(Key::Integer(3), OwnedValue::Blob(note_to_bytes(&note))).unwrap();
+    kv.persist_to_file(test_file).unwrap();
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let id = store.create("Neu".into(), "".into()).unwrap();
+    assert_eq!(id, 4);
+    assert_eq!(store.get(3).unwrap().unwrap().title, "Fremd");
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_get_meta() {
+    let test_file = "test_notes_get_meta.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let id = store
+        .create_with_tags("Meta".into(), "langer Inhalt".into(), vec!["t1".into(), "t2".into()])
+        .unwrap();
+
+    let meta = store.get_meta(id).unwrap().unwrap();
+    assert_eq!(meta.id, id);
+    assert_eq!(meta.title, "Meta");
+    assert_eq!(meta.tags, vec!["t1".to_string(), "t2".to_string()]);
+    assert_eq!(meta.updated_at, store.get(id).unwrap().unwrap().updated_at);
+
+    assert!(store.get_meta(id + 1).unwrap().is_none());
+
+    store.delete(id).unwrap();
+    assert!(store.get_meta(id).unwrap().is_none());
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_truncated_note_error_has_detail() {
+    use kv_store::notes::{note_from_bytes, note_to_bytes, Note};
+    use kv_store::{DecodeError, KvError};
+
+    let note = Note {
+        id: 1,
+        title: "Abgeschnitten".into(),
+        body: "Inhalt".into(),
+        tags: vec![],
+        updated_at: 0,
+        links: vec![],
+    };
+    let bytes = note_to_bytes(&note);
+
+    let err = note_from_bytes(&bytes[..bytes.len() - 4]).err().unwrap();
+    match err {
+        KvError::Corrupted(DecodeError::NoteDecode(ref msg)) => {
+            assert!(!msg.is_empty());
+            // Die Meldung enthält die Ursache, nicht nur "note decoding failed"
+            let text = err.to_string();
+            assert!(text.starts_with("storage data is corrupted: note decoding failed: "));
+            assert!(text.ends_with(msg.as_str()));
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+}
+
+#[test]
+fn test_all_tags() {
+    let test_file = "test_notes_all_tags.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    assert!(store.all_tags().unwrap().is_empty());
+
+    let tags = |t: &[&str]| t.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    store.create_with_tags("A".into(), "".into(), tags(&["rust", "arbeit"])).unwrap();
+    store.create_with_tags("B".into(), "".into(), tags(&["privat", "rust"])).unwrap();
+    let c = store.create_with_tags("C".into(), "".into(), tags(&["arbeit", "Zeug"])).unwrap();
+    store.create("D".into(), "ohne Tags".into()).unwrap();
+
+    assert_eq!(store.all_tags().unwrap(), vec!["Zeug", "arbeit", "privat", "rust"]);
+
+    // Tags gelöschter Notizen verschwinden
+    store.delete(c).unwrap();
+    assert_eq!(store.all_tags().unwrap(), vec!["arbeit", "privat", "rust"]);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_markdown_roundtrip() {
+    let test_file = "test_notes_markdown.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let tags = vec!["rust".to_string(), "idee".to_string()];
+    let body = "Erste Zeile\n\n- Punkt\nTags: im Text bleibt Text\nletzte Zeile".to_string();
+    let id = store.create_with_tags("Plan".into(), body.clone(), tags.clone()).unwrap();
+
+    let md = store.export_markdown(id).unwrap().unwrap();
+    assert_eq!(md, format!("# Plan\n\n{body}\n\nTags: rust, idee\n"));
+
+    let copy = store.import_markdown(&md).unwrap();
+    let note = store.get(copy).unwrap().unwrap();
+    assert_eq!(note.title, "Plan");
+    assert_eq!(note.body, body);
+    assert_eq!(note.tags, tags);
+
+    // Ohne Tags keine Tags-Zeile, leerer Body bleibt leer
+    let bare = store.create("Leer".into(), String::new()).unwrap();
+    let md = store.export_markdown(bare).unwrap().unwrap();
+    assert_eq!(md, "# Leer\n\n\n");
+    let copy = store.import_markdown(&md).unwrap();
+    let note = store.get(copy).unwrap().unwrap();
+    assert_eq!((note.title.as_str(), note.body.as_str()), ("Leer", ""));
+    assert!(note.tags.is_empty());
+
+    assert_eq!(store.export_markdown(9999).unwrap(), None);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_import_markdown_without_heading() {
+    use kv_store::notes::MARKDOWN_DEFAULT_TITLE;
+
+    let test_file = "test_notes_markdown_plain.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let id = store.import_markdown("Nur Text\nohne Überschrift\n").unwrap();
+    let note = store.get(id).unwrap().unwrap();
+    assert_eq!(note.title, MARKDOWN_DEFAULT_TITLE);
+    assert_eq!(note.body, "Nur Text\nohne Überschrift");
+
+    // "## " ist keine Titelzeile
+    let id = store.import_markdown("## Abschnitt\nText\n\nTags: a, , b").unwrap();
+    let note = store.get(id).unwrap().unwrap();
+    assert_eq!(note.title, MARKDOWN_DEFAULT_TITLE);
+    assert_eq!(note.body, "## Abschnitt\nText");
+    assert_eq!(note.tags, vec!["a", "b"]);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_backup_and_restore() {
+    let test_file = "test_notes_backup_live.bin";
+    let backup_file = "test_notes_backup_copy.bin";
+    let restored_file = "test_notes_backup_restored.bin";
+    for f in [test_file, backup_file, restored_file] {
+        let _ = fs::remove_file(f);
+    }
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    let a = store.create("A".into(), "erste".into()).unwrap();
+    let b = store.create("B".into(), "zweite".into()).unwrap();
+    // Nicht gespeichert: das Backup nimmt den Stand im Speicher
+    store.backup(backup_file).unwrap();
+
+    store.delete(a).unwrap();
+    store.create("C".into(), "nach dem Backup".into()).unwrap();
+    store.save(test_file).unwrap();
+
+    let mut restored = NoteStore::restore(backup_file, restored_file).unwrap();
+    let titles: Vec<String> = restored.list_meta().unwrap().into_iter().map(|m| m.title).collect();
+    assert_eq!(titles, vec!["A", "B"]);
+    assert_eq!(restored.get(b).unwrap().unwrap().body, "zweite");
+
+    // Das Ziel ist eine vollständige Kopie, neue IDs kollidieren nicht
+    let reopened = NoteStore::open(restored_file).unwrap();
+    assert_eq!(reopened.count(), 2);
+    let c = restored.create("D".into(), "".into()).unwrap();
+    assert!(c > b);
+
+    assert!(NoteStore::restore("test_notes_backup_missing.bin", restored_file).is_err());
+
+    for f in [test_file, backup_file, restored_file] {
+        let _ = fs::remove_file(f);
+    }
+}
+
+#[test]
+fn test_two_instances_get_distinct_ids() {
+    let test_file = "test_notes_two_instances.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut seed = NoteStore::open(test_file).unwrap();
+    seed.create("Basis".into(), "".into()).unwrap();
+    seed.save(test_file).unwrap();
+
+    // Beide Instanzen öffnen denselben Stand und speichern in dieselbe Datei
+    let mut a = NoteStore::open(test_file).unwrap();
+    let mut b = NoteStore::open(test_file).unwrap();
+
+    let id_a = a.create("Von A".into(), "".into()).unwrap();
+    a.save(test_file).unwrap();
+
+    // B sieht beim Anlegen die von A gespeicherte Notiz
+    let id_b = b.create("Von B".into(), "".into()).unwrap();
+    assert_ne!(id_a, id_b);
+    b.save(test_file).unwrap();
+
+    // A speichert erneut und überschreibt dabei die Notiz von B nicht
+    let id_c = a.create("Noch eine von A".into(), "".into()).unwrap();
+    assert!(id_c > id_b);
+    a.save(test_file).unwrap();
+
+    let store = NoteStore::open(test_file).unwrap();
+    let titles: Vec<String> = store.list_meta().unwrap().into_iter().map(|m| m.title).collect();
+    assert_eq!(titles, vec!["Basis", "Von A", "Von B", "Noch eine von A"]);
+    let ids: Vec<u64> = store.list_meta().unwrap().into_iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec![1, id_a, id_b, id_c]);
+
+    // Gelöschte Notizen kommen durch das Zusammenführen nicht zurück
+    assert!(b.delete(1).unwrap());
+    b.save(test_file).unwrap();
+    let store = NoteStore::open(test_file).unwrap();
+    assert!(store.get(1).unwrap().is_none());
+    assert_eq!(store.count(), 3);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_open_with_start_id() {
+    let test_file = "test_notes_start_id.bin";
+    let _ = fs::remove_file(test_file);
+
+    let mut store = NoteStore::open_with_start_id(test_file, 1000).unwrap();
+    assert_eq!(store.create("Erste".into(), "".into()).unwrap(), 1000);
+    assert_eq!(store.create("Zweite".into(), "".into()).unwrap(), 1001);
+    store.save(test_file).unwrap();
+
+    // Ein kleinerer Start-Wert setzt den Zähler nicht zurück
+    let mut reopened = NoteStore::open_with_start_id(test_file, 5).unwrap();
+    assert_eq!(reopened.create("Dritte".into(), "".into()).unwrap(), 1002);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_id_space_exhausted() {
+    use kv_store::KvError;
+
+    let test_file = "test_notes_id_exhausted.bin";
+    let _ = fs::remove_file(test_file);
+
+    // Zähler direkt vor dem Überlauf
+    let mut store = NoteStore::open_with_start_id(test_file, i64::MAX as u64).unwrap();
+    assert_eq!(store.create("Letzte".into(), "".into()).unwrap(), i64::MAX as u64);
+    assert!(matches!(
+        store.create("Zu viel".into(), "".into()),
+        Err(KvError::IdSpaceExhausted)
+    ));
+    store.save(test_file).unwrap();
+
+    // Auch nach dem Neuladen bleibt der Zähler erschöpft
+    let mut reopened = NoteStore::open(test_file).unwrap();
+    assert!(matches!(
+        reopened.create("Zu viel".into(), "".into()),
+        Err(KvError::IdSpaceExhausted)
+    ));
+    assert_eq!(reopened.list_meta().unwrap().len(), 1);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_internal_keys_not_exposed() {
+    let mut store = NoteStore::open("test_notes_internal_keys_unused.bin").unwrap();
+    store.create("Eins".into(), "Text".into()).unwrap();
+    store.create_with_tags("Zwei".into(), "".into(), vec!["tag".into()]).unwrap();
+
+    #[cfg(feature = "serde")]
+    {
+        let json = store.export_json().unwrap();
+        assert!(!json.contains("__"));
+        let notes: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0]["title"], "Eins");
+    }
+
+    let titles: Vec<String> = store.list_meta().unwrap().into_iter().map(|m| m.title).collect();
+    assert_eq!(titles, vec!["Eins", "Zwei"]);
+    assert_eq!(store.count(), 2);
+    assert_eq!(store.all_tags().unwrap(), vec!["tag"]);
+}
+
+#[test]
+fn test_legacy_meta_keys_are_migrated() {
+    use kv_store::notes::{note_to_bytes, Note};
+    use kv_store::{Key, KvStore, OwnedValue};
+
+    let test_file = "test_notes_legacy_meta.bin";
+    let _ = fs::remove_file(test_file);
+
+    // Altes Layout: Zähler unter "__meta_next_id"
+    let note = Note {
+        id: 3,
+        title: "Alt".into(),
+        body: "".into(),
+        tags: vec![],
+        updated_at: 0,
+        links: vec![],
+    };
+    let mut kv = KvStore::new();
+    kv.insert(Key::Integer(3), OwnedValue::Blob(note_to_bytes(&note))).unwrap();
+    kv.insert(Key::Text("__meta_next_id".into()), OwnedValue::Integer(10)).unwrap();
+    kv.persist_to_file(test_file).unwrap();
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    assert_eq!(store.create("Neu".into(), "".into()).unwrap(), 10);
+    store.save(test_file).unwrap();
+
+    let kv = KvStore::load_from_file(test_file).unwrap();
+    assert!(kv.keys().all(|k| !matches!(k, Key::Text(s) if s.starts_with("__meta"))));
+    assert_eq!(NoteStore::open(test_file).unwrap().count(), 2);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_list_meta_sorted() {
+    use kv_store::notes::{note_to_bytes, Note, NoteSort};
+    use kv_store::{Key, KvStore, OwnedValue};
+
+    let test_file = "test_notes_sorted.bin";
+    let _ = fs::remove_file(test_file);
+
+    // Zeitstempel direkt setzen, create würde die aktuelle Zeit nehmen
+    let mut kv = KvStore::new();
+    for (id, title, updated_at) in [(1, "banane", 300), (2, "Apfel", 100), (3, "citrus", 200), (4, "apfel", 300)] {
+        let note = Note {
+            id,
+            title: title.into(),
+            body: "".into(),
+            tags: vec![],
+            updated_at,
+            links: vec![],
+        };
+        kv.insert(Key::Integer(id as i64), OwnedValue::Blob(note_to_bytes(&note))).unwrap();
+    }
+    kv.persist_to_file(test_file).unwrap();
+    let store = NoteStore::open(test_file).unwrap();
+
+    let ids = |order| -> Vec<u64> {
+        store.list_meta_sorted(order).unwrap().into_iter().map(|m| m.id).collect()
+    };
+    assert_eq!(ids(NoteSort::ById), vec![1, 2, 3, 4]);
+    assert_eq!(ids(NoteSort::ByUpdatedDesc), vec![1, 4, 3, 2]);
+    assert_eq!(ids(NoteSort::ByTitle), vec![2, 4, 1, 3]);
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_unexpected_value_shapes_report_type_mismatch() {
+    use kv_store::{Key, KvError, KvStore, OwnedValue, ValueType};
+
+    let test_file = "test_notes_value_shapes.bin";
+    let _ = fs::remove_file(test_file);
+
+    // Text statt Notiz-Blob unter einer Integer-Id
+    let mut kv = KvStore::new();
+    kv.insert(Key::Integer(1), OwnedValue::Text("keine Notiz".into())).unwrap();
+    kv.persist_to_file(test_file).unwrap();
+
+    let store = NoteStore::open(test_file).unwrap();
+    let mismatch = |res: Result<_, KvError>| {
+        matches!(res, Err(KvError::TypeMismatch { expected: ValueType::Blob, found: ValueType::Text }))
+    };
+    assert!(mismatch(store.get(1).map(|_| ())));
+    assert!(mismatch(store.list_meta().map(|_| ())));
+
+    // Zähler ist keine Zahl
+    let mut kv = KvStore::new();
+    kv.insert(Key::Text("__notes:next_id".into()), OwnedValue::Bool(true)).unwrap();
+    kv.persist_to_file(test_file).unwrap();
+    assert!(matches!(
+        NoteStore::open(test_file),
+        Err(KvError::TypeMismatch { expected: ValueType::Integer, found: ValueType::Bool })
+    ));
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_autosave_persists_without_save() {
+    let test_file = "test_notes_autosave.bin";
+    let _ = fs::remove_file(test_file);
+
+    let (kept, gone) = {
+        let mut store = NoteStore::open_autosave(test_file).unwrap();
+        let kept = store.create("Bleibt".to_string(), "alt".to_string()).unwrap();
+        let gone = store.create("Weg".to_string(), "x".to_string()).unwrap();
+
+        let mut note = store.get(kept).unwrap().unwrap();
+        note.body = "neu".to_string();
+        store.update(note).unwrap();
+        assert!(store.delete(gone).unwrap());
+        (kept, gone)
+        // Kein save(): Drop und die Mutationen schreiben selbst
+    };
+
+    let reopened = NoteStore::open(test_file).unwrap();
+    assert_eq!(reopened.get(kept).unwrap().unwrap().body, "neu");
+    assert!(reopened.get(gone).unwrap().is_none());
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_plain_open_does_not_autosave() {
+    let test_file = "test_notes_no_autosave.bin";
+    let _ = fs::remove_file(test_file);
+
+    {
+        let mut store = NoteStore::open(test_file).unwrap();
+        store.create("A".to_string(), "B".to_string()).unwrap();
+    }
+    assert!(!std::path::Path::new(test_file).exists());
+
+    let _ = fs::remove_file(test_file);
+}
+
+#[test]
+fn test_compact_shrinks_file_and_keeps_notes() {
+    use kv_store::notes::{note_to_bytes, Note};
+    use kv_store::{Key, KvStore, OwnedValue};
+
+    let test_file = "test_notes_compact.bin";
+    let _ = fs::remove_file(test_file);
+
+    // Datei per append_to_file aufbauen, damit alte Versionen auf der Platte liegen
+    let mut kv = KvStore::new();
+    let mut seq = 0;
+    for version in 0..50 {
+        for id in 1..=3u64 {
+            let note = Note {
+                id,
+                title: format!("Notiz {id}"),
+                body: format!("Version {version}"),
+                tags: vec![],
+                updated_at: 0,
+                links: vec![],
+            };
+            kv.insert(Key::Integer(id as i64), OwnedValue::Blob(note_to_bytes(&note)))
+                .unwrap();
+        }
+        seq = kv.append_to_file(test_file, seq).unwrap();
+    }
+
+    let mut store = NoteStore::open(test_file).unwrap();
+    for _ in 0..20 {
+        let mut note = store.get(2).unwrap().unwrap();
+        note.body.push('!');
+        store.update(note).unwrap();
+    }
+    let before = fs::metadata(test_file).unwrap().len();
+
+    store.compact().unwrap();
+    let after = fs::metadata(test_file).unwrap().len();
+    assert!(after < before, "{after} >= {before}");
+
+    for id in 1..=3 {
+        let note = store.get(id).unwrap().unwrap();
+        assert_eq!(note.title, format!("Notiz {id}"));
+    }
+    assert_eq!(store.get(2).unwrap().unwrap().body, format!("Version 49{}", "!".repeat(20)));
+
+    let reopened = NoteStore::open(test_file).unwrap();
+    assert_eq!(reopened.get(1).unwrap().unwrap().body, "Version 49");
+    assert_eq!(reopened.get(2).unwrap().unwrap().body, format!("Version 49{}", "!".repeat(20)));
+
+    let _ = fs::remove_file(test_file);
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "serde")]
+
+use kv_store::{BorrowedValue, Key, OwnedValue};
+
+fn all_values() -> Vec<OwnedValue> {
+    vec![
+        OwnedValue::Integer(-42),
+        OwnedValue::Bool(true),
+        OwnedValue::Text("hällo \"welt\"".to_string()),
+        OwnedValue::Blob(vec![0, 1, 2, 250, 255]),
+        OwnedValue::Blob(vec![]),
+        OwnedValue::Timestamp(1_700_000_000),
+    ]
+}
+
+#[test]
+fn test_key_roundtrip_json_and_bincode() {
+    for key in [Key::Integer(i64::MIN), Key::Text("schlüssel".to_string())] {
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(serde_json::from_str::<Key>(&json).unwrap(), key);
+
+        let bin = bincode::serialize(&key).unwrap();
+        assert_eq!(bincode::deserialize::<Key>(&bin).unwrap(), key);
+    }
+}
+
+#[test]
+fn test_interned_key_serializes_like_text() {
+    let text = Key::Text("schlüssel".to_string());
+    let interned = Key::Interned("schlüssel".into());
+
+    assert_eq!(serde_json::to_string(&interned).unwrap(), serde_json::to_string(&text).unwrap());
+    let bin = bincode::serialize(&interned).unwrap();
+    assert_eq!(bin, bincode::serialize(&text).unwrap());
+    assert!(matches!(bincode::deserialize::<Key>(&bin).unwrap(), Key::Text(_)));
+}
+
+#[test]
+fn test_owned_value_roundtrip_json_and_bincode() {
+    for value in all_values() {
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<OwnedValue>(&json).unwrap(), value);
+
+        let bin = bincode::serialize(&value).unwrap();
+        assert_eq!(bincode::deserialize::<OwnedValue>(&bin).unwrap(), value);
+    }
+}
+
+#[test]
+fn test_blob_representation() {
+    let value = OwnedValue::Blob(vec![1, 2, 3]);
+
+    // JSON: base64-String
+    assert_eq!(serde_json::to_string(&value).unwrap(), r#"{"Blob":"AQID"}"#);
+
+    // bincode: Tag (u32) + Länge (u64) + rohe Bytes
+    let bin = bincode::serialize(&value).unwrap();
+    assert_eq!(bin.len(), 4 + 8 + 3);
+    assert_eq!(&bin[12..], &[1, 2, 3]);
+}
+
+#[test]
+fn test_borrowed_value_matches_owned_encoding() {
+    for value in all_values() {
+        let borrowed = match &value {
+            OwnedValue::Integer(i) => BorrowedValue::Integer(*i),
+            OwnedValue::Bool(b) => BorrowedValue::Bool(*b),
+            OwnedValue::Text(s) => BorrowedValue::Text(s),
+            OwnedValue::Blob(b) => BorrowedValue::Blob(b),
+            OwnedValue::Timestamp(t) => BorrowedValue::Timestamp(*t),
+        };
+
+        let json = serde_json::to_string(&borrowed).unwrap();
+        assert_eq!(json, serde_json::to_string(&value).unwrap());
+        assert_eq!(serde_json::from_str::<OwnedValue>(&json).unwrap(), value);
+
+        // bincode kann direkt in den Eingabepuffer zurückleihen
+        let bin = bincode::serialize(&borrowed).unwrap();
+        let back: BorrowedValue = bincode::deserialize(&bin).unwrap();
+        assert_eq!(back, borrowed);
+    }
+}
+
+#[test]
+fn test_export_import_json_roundtrip() {
+    use kv_store::KvStore;
+
+    let mut kv = KvStore::new();
+    kv.insert(Key::Text("int".into()), OwnedValue::Integer(-7)).unwrap();
+    kv.insert(Key::Integer(2), OwnedValue::Bool(false)).unwrap();
+    kv.insert(Key::Integer(-1), OwnedValue::Text("zeile\n\"zitat\"".into())).unwrap();
+    // Kein gültiges UTF-8
+    kv.insert(Key::Text("blob".into()), OwnedValue::Blob(vec![0xff, 0xfe, 0x00, 0x80])).unwrap();
+
+    let json = kv.export_json().unwrap();
+    assert!(json.contains(r#"{"key":{"type":"integer","value":-1},"value":{"type":"text","#));
+    assert!(json.contains(r#""value":{"type":"blob","value":"//4AgA=="}"#));
+
+    let back = KvStore::import_json(&json).unwrap();
+    assert_eq!(back.keys().count(), 4);
+    for key in kv.keys() {
+        assert_eq!(back.get_owned(key).unwrap(), kv.get_owned(key).unwrap());
+    }
+
+    // Sortierte Ausgabe ist deterministisch
+    assert_eq!(back.export_json().unwrap(), json);
+
+    assert!(KvStore::import_json("[{\"key\": 1}]").is_err());
+}
+
+#[test]
+fn test_export_json_formats_timestamps() {
+    use kv_store::KvStore;
+
+    let mut kv = KvStore::new();
+    kv.insert(Key::Text("t".into()), OwnedValue::Timestamp(951_782_400)).unwrap();
+    kv.insert(Key::Text("i".into()), OwnedValue::Integer(951_782_400)).unwrap();
+
+    // Schalttag 2000, als ISO-8601 statt als Zahl
+    let json = kv.export_json().unwrap();
+    assert!(json.contains(r#""value":{"type":"timestamp","value":"2000-02-29T00:00:00Z"}"#));
+    assert!(json.contains(r#""value":{"type":"integer","value":951782400}"#));
+
+    let back = KvStore::import_json(&json).unwrap();
+    assert_eq!(back.get_owned(&Key::Text("t".into())).unwrap(), Some(OwnedValue::Timestamp(951_782_400)));
+
+    for bad in ["2000-02-30T00:00:00Z", "1969-12-31T23:59:59Z", "2000-02-29 00:00:00"] {
+        let json = format!(r#"[{{"key":{{"type":"text","value":"t"}},"value":{{"type":"timestamp","value":"{bad}"}}}}]"#);
+        assert!(KvStore::import_json(&json).is_err(), "{bad}");
+    }
+}